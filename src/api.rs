@@ -1,37 +1,163 @@
 use axum::{
-    extract::{Query, State},
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Path, Query, Request, State},
     http::StatusCode,
     response::Json,
     routing::{get, post},
     Router,
 };
-use std::collections::HashMap;
+use futures::stream::{self, StreamExt};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 
-use crate::firecrawl_client::FirecrawlClient;
-use crate::models::{ErrorResponse, SearchRequest, SearchResponse};
-use crate::parser::parse_junkyard_page;
-use crate::pick_n_pull::PicknPullSearch;
+use crate::catalog;
+use crate::catalog_sync;
+use crate::data_file::DataFile;
+use crate::fetcher::PageFetcher;
+use crate::firecrawl_client::{CrawlData, Error as FetchError, ScrapeFormat};
+use crate::geo::{annotate_distances, filter_and_sort, ZipCentroids};
+use crate::interchange::{query_years, Interchange, Part};
+use crate::inventory::{vehicle_key, HistoryLookup, InventoryStore};
+use crate::notifier::Notifier;
+use crate::models::{
+    CreateWatchRequest, ErrorResponse, FieldError, JunkyardItem, ParserMode, PartResult,
+    PartSearchRequest, PartSearchResponse, SavedSearch, SearchRadius, SearchRequest,
+    SearchResponse, SortOrder, StoreInventoryChanges, StoreLocation, TripPlanRequest, TripPlanResponse,
+    VehicleHistory, WatchRunResult,
+};
+use crate::normalize;
+use crate::parser::{is_results_page, parse_locations, parse_page, retain_store};
+use crate::pick_n_pull::{LookupError, PicknPullSearch, YearRange};
+use crate::scheduler::check_watch;
+use crate::stores::StoreDirectory;
+use crate::trips;
+use crate::validation::{validate_part_search, validate_search, validate_trip};
+use crate::watchlist::WatchStore;
+
+/// How many result pages a multi-model search crawls at once.
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+/// A JSON request body whose problems (malformed JSON, a missing field, a value of
+/// the wrong type) are reported as validation errors naming the field.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: serde::de::DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<serde_json::Value>::from_request(request, state)
+            .await
+            .map_err(json_rejection)?;
+        serde_path_to_error::deserialize(body)
+            .map(ValidJson)
+            .map_err(|error| {
+                let message = error.inner().to_string();
+                // Missing fields are reported by the object holding them
+                let missing = message
+                    .strip_prefix("missing field `")
+                    .and_then(|rest| rest.split_once('`'))
+                    .map(|(name, _)| name.to_string());
+                let path = error.path().to_string();
+                match missing {
+                    Some(name) if path == "." => invalid_field(&name, "required", message),
+                    Some(name) => invalid_field(&format!("{}.{}", path, name), "required", message),
+                    None => invalid_field(&path, "invalid_format", message),
+                }
+            })
+    }
+}
 
 #[derive(Clone)]
 pub struct AppState {
-    pub firecrawl_client: Arc<FirecrawlClient>,
+    pub fetcher: Arc<dyn PageFetcher>,
     pub pick_n_pull: Arc<PicknPullSearch>,
+    pub inventory: Option<Arc<InventoryStore>>,
+    pub watches: Option<Arc<WatchStore>>,
+    pub notifiers: Vec<Arc<dyn Notifier>>,
+    pub interchange: Arc<Interchange>,
+    pub stores: Option<Arc<StoreDirectory>>,
+    pub zip_centroids: Arc<ZipCentroids>,
 }
 
-pub fn create_app(firecrawl_client: FirecrawlClient) -> Router {
-    let state = AppState {
-        firecrawl_client: Arc::new(firecrawl_client),
-        pick_n_pull: Arc::new(PicknPullSearch::new()),
-    };
+impl AppState {
+    pub fn new(fetcher: Arc<dyn PageFetcher>, pick_n_pull: PicknPullSearch) -> Self {
+        Self {
+            fetcher,
+            pick_n_pull: Arc::new(pick_n_pull),
+            inventory: None,
+            watches: None,
+            notifiers: Vec::new(),
+            interchange: Arc::new(Interchange::bundled()),
+            stores: None,
+            zip_centroids: Arc::new(ZipCentroids::bundled()),
+        }
+    }
+
+    /// Use other interchange data for `/parts/search` than the bundled set.
+    pub fn with_interchange(mut self, interchange: Interchange) -> Self {
+        self.interchange = Arc::new(interchange);
+        self
+    }
+
+    /// Persist every search result so vehicles get first/last seen tracking.
+    pub fn with_inventory(mut self, inventory: InventoryStore) -> Self {
+        self.inventory = Some(Arc::new(inventory));
+        self
+    }
+
+    /// Enable the saved search (`/watches`) endpoints.
+    pub fn with_watches(mut self, watches: WatchStore) -> Self {
+        self.watches = Some(Arc::new(watches));
+        self
+    }
+
+    /// Keep a directory of yards from crawled pages and enable `/stores`.
+    pub fn with_stores(mut self, stores: StoreDirectory) -> Self {
+        self.stores = Some(Arc::new(stores));
+        self
+    }
+
+    /// Use another zip centroid dataset than the bundled one for distances.
+    pub fn with_zip_centroids(mut self, centroids: ZipCentroids) -> Self {
+        self.zip_centroids = Arc::new(centroids);
+        self
+    }
+
+    /// Tell `notifier` about new arrivals found by saved searches.
+    pub fn with_notifier(mut self, notifier: impl Notifier + 'static) -> Self {
+        self.notifiers.push(Arc::new(notifier));
+        self
+    }
+}
 
+pub fn create_app(state: AppState) -> Router {
     Router::new()
         .route("/search", post(search_vehicles))
         .route("/search", get(search_vehicles_get))
         .route("/health", get(health_check))
         .route("/supported-makes", get(get_supported_makes))
         .route("/supported-models", get(get_supported_models))
+        .route("/watches", post(create_watch).get(list_watches))
+        .route("/watches/run", post(run_all_watches))
+        .route("/watches/:id", get(get_watch).delete(delete_watch))
+        .route("/watches/:id/run", post(run_watch))
+        .route("/watches/:id/events", get(list_watch_events))
+        .route("/events", get(list_events))
+        .route("/stores", get(list_stores))
+        .route("/stores/:id", get(get_store))
+        .route("/stores/:id/inventory", get(get_store_inventory))
+        .route("/vehicles/:id/history", get(get_vehicle_history))
+        .route("/parts/search", post(search_parts).get(search_parts_get))
+        .route("/trips/plan", post(plan_trip))
+        .route("/admin/catalog/reload", post(reload_catalog))
         .with_state(state)
         .layer(CorsLayer::permissive())
 }
@@ -39,7 +165,7 @@ pub fn create_app(firecrawl_client: FirecrawlClient) -> Router {
 // POST /search - Main search endpoint
 pub async fn search_vehicles(
     State(state): State<AppState>,
-    Json(request): Json<SearchRequest>,
+    ValidJson(request): ValidJson<SearchRequest>,
 ) -> Result<Json<SearchResponse>, (StatusCode, Json<ErrorResponse>)> {
     perform_search(state, request).await
 }
@@ -49,74 +175,63 @@ pub async fn search_vehicles_get(
     State(state): State<AppState>,
     Query(params): Query<HashMap<String, String>>,
 ) -> Result<Json<SearchResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Parse query parameters into SearchRequest
+    // Parse query parameters into SearchRequest; the values are validated with the rest of the search
     let make = params.get("make")
-        .ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    success: false,
-                    error: "Missing 'make' parameter".to_string(),
-                }),
-            )
-        })?;
+        .ok_or_else(|| invalid_field("make", "required", "Missing 'make' parameter"))?;
 
-    let model = params.get("model")
-        .ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    success: false,
-                    error: "Missing 'model' parameter".to_string(),
-                }),
-            )
-        })?;
+    // Optional; several models are comma separated, none searches the whole make
+    let models: Vec<String> = params.get("model")
+        .map(|value| value.split(',').map(str::trim).filter(|m| !m.is_empty()).map(String::from).collect())
+        .unwrap_or_default();
 
-    let year_min = params.get("year_min")
-        .and_then(|s| s.parse::<u32>().ok())
-        .ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    success: false,
-                    error: "Missing or invalid 'year_min' parameter".to_string(),
-                }),
-            )
-        })?;
+    // Optional; `year=2004-` (or `2004+`) is the same as `year_min=2004`
+    let (mut year_min, mut year_max) = match params.get("year") {
+        Some(value) => parse_year_range(value).ok_or_else(|| {
+            invalid_field("year", "invalid_format", "Expected e.g. 2004, 2000-2006, 2004- or -2006")
+        })?,
+        None => (None, None),
+    };
+    if let Some(value) = params.get("year_min") {
+        year_min = Some(parse_number_param("year_min", value)?);
+    }
+    if let Some(value) = params.get("year_max") {
+        year_max = Some(parse_number_param("year_max", value)?);
+    }
 
-    let year_max = params.get("year_max")
-        .and_then(|s| s.parse::<u32>().ok())
-        .ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    success: false,
-                    error: "Missing or invalid 'year_max' parameter".to_string(),
-                }),
-            )
-        })?;
+    // A single yard (`store=newark`) stands in for the zip code
+    let store_id = params.get("store").or_else(|| params.get("store_id")).cloned();
+    let zip_code = match (params.get("zip_code"), &store_id) {
+        (Some(zip_code), _) => zip_code,
+        (None, Some(_)) => "",
+        (None, None) => {
+            return Err(invalid_field("zip_code", "required", "Missing 'zip_code' or 'store' parameter"))
+        }
+    };
 
-    let zip_code = params.get("zip_code")
-        .ok_or_else(|| {
-            (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    success: false,
-                    error: "Missing 'zip_code' parameter".to_string(),
-                }),
-            )
-        })?;
+    let distance = match params.get("distance") {
+        Some(value) => Some(parse_distance_param(value)?),
+        None => None,
+    };
 
-    let distance = params.get("distance")
-        .and_then(|s| s.parse::<u32>().ok());
+    let parser = match params.get("parser") {
+        Some(value) => Some(value.parse::<ParserMode>()
+            .map_err(|error| invalid_field("parser", "unsupported_value", error))?),
+        None => None,
+    };
+
+    let (sort, max_miles) = parse_sort_params(&params)?;
 
     let request = SearchRequest {
         make: make.clone(),
-        model: model.clone(),
+        models,
         year_min,
         year_max,
-        zip_code: zip_code.clone(),
+        zip_code: zip_code.to_string(),
         distance,
+        parser,
+        store_id,
+        sort,
+        max_miles,
     };
 
     perform_search(state, request).await
@@ -126,75 +241,473 @@ async fn perform_search(
     state: AppState,
     request: SearchRequest,
 ) -> Result<Json<SearchResponse>, (StatusCode, Json<ErrorResponse>)> {
-    // Validate year range
-    if request.year_min > request.year_max {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                success: false,
-                error: "year_min cannot be greater than year_max".to_string(),
-            }),
-        ));
-    }
+    let SearchResults { vehicles, warnings } = run_search(&state, &request).await?;
+    let total_found = vehicles.len();
 
-    // Default distance to 50 miles if not provided
-    let distance = request.distance.unwrap_or(50);
+    Ok(Json(SearchResponse {
+        success: true,
+        vehicles,
+        search_params: request,
+        total_found,
+        warnings,
+    }))
+}
 
-    // Generate search URL
-    let search_url = match state.pick_n_pull.generate_search_url(
-        &request.make,
-        &request.model,
-        &request.zip_code,
-        distance,
-        (request.year_min, request.year_max),
-    ) {
-        Ok(url) => url,
-        Err(error) => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    success: false,
-                    error,
-                }),
-            ))
+/// Looks `make` up on the site's search form and adds it with its models to the
+/// catalog, saving the catalog file when there is one. Returns whether the site
+/// listed the make.
+async fn learn_make(state: &AppState, make: &str) -> bool {
+    let pick_n_pull = &state.pick_n_pull;
+    if !pick_n_pull.claim_site_lookup(make) {
+        return false;
+    }
+    let found = match catalog_sync::sync_make(state.fetcher.as_ref(), pick_n_pull.base_url(), make).await {
+        Ok(Some(found)) => found,
+        Ok(None) => return false,
+        Err(e) => {
+            println!("Could not look up make {} on the site: {}", make, e);
+            return false;
         }
     };
 
-    // Crawl the webpage
-    let crawl_response = match state.firecrawl_client.crawl_webpage(&search_url).await {
-        Ok(response) => response,
-        Err(e) => {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    success: false,
-                    error: format!("Failed to crawl webpage: {}", e),
-                }),
-            ))
+    pick_n_pull.add_make(&found);
+    println!("📚 Added {} ({} models) to the catalog from the site", found.name, found.models.len());
+    if let Some(path) = pick_n_pull.catalog_path() {
+        if let Err(e) = pick_n_pull.catalog().save(path) {
+            println!("Could not save the catalog to {}: {}", path.display(), e);
         }
+    }
+    true
+}
+
+/// What one search found.
+#[derive(Debug)]
+pub(crate) struct SearchResults {
+    pub vehicles: Vec<JunkyardItem>,
+    /// Caveats about the results, e.g. that distances couldn't be computed
+    pub warnings: Vec<String>,
+}
+
+/// Builds the search URLs, fetches and parses the pages and records the listings.
+///
+/// A search over several models (or a whole make) is one URL per model; these are
+/// crawled concurrently and merged, dropping vehicles listed more than once.
+/// Shared by the `/search` handlers and the saved search runner.
+pub(crate) async fn run_search(
+    state: &AppState,
+    request: &SearchRequest,
+) -> Result<SearchResults, ApiError> {
+    validate_search(request).map_err(validation_error)?;
+
+    // A store search covers the smallest radius around the yard's own zip; other
+    // searches default to 50 miles around the given zip
+    let store = match &request.store_id {
+        Some(id) => Some(resolve_store(state, id)?),
+        None => None,
+    };
+    let (zip_code, distance) = match &store {
+        Some(location) => (location.zip.as_str(), SearchRadius::Miles10),
+        None => (request.zip_code.as_str(), request.distance.unwrap_or_default()),
     };
 
-    // Parse the response
-    let vehicles = if let Some(data) = &crawl_response.data {
-        if let Some(markdown) = &data.markdown {
-            parse_junkyard_page(markdown, &search_url)
-        } else {
-            Vec::new()
+    // Generate one search URL per model. A make or model the catalog doesn't know
+    // may just be newer than it, so the site's search form is asked before giving up
+    let years = (request.year_min, request.year_max);
+    let generate = || {
+        state.pick_n_pull.generate_search_urls(&request.make, &request.models, zip_code, distance, years)
+    };
+    let search_urls = match generate() {
+        Ok(urls) => urls,
+        Err(_) if learn_make(state, &request.make).await => generate().map_err(lookup_error)?,
+        Err(error) => return Err(lookup_error(error)),
+    };
+
+    // Only ask the fetcher for the formats the chosen parser needs
+    let parser_mode = request.parser.unwrap_or_default();
+    let formats = scrape_formats_for(parser_mode);
+
+    // Crawl the pages a few at a time; any failure, or a page that isn't a results
+    // page, fails the whole search so a partial listing is never recorded as
+    // vehicles having gone
+    let pages: Vec<_> = stream::iter(search_urls.clone())
+        .map(|url| {
+            let fetcher = state.fetcher.clone();
+            let formats = formats.clone();
+            async move { fetcher.fetch_page(&url, &formats).await }
+        })
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+
+    let seen_at = chrono::Utc::now();
+    let mut vehicles = Vec::new();
+    let mut seen_keys = HashSet::new();
+    for (search_url, page) in search_urls.iter().zip(pages) {
+        let crawl_response = page.map_err(|e| {
+            (
+                fetch_error_status(&e),
+                Json(ErrorResponse::new(format!("Failed to crawl webpage: {}", e))),
+            )
+        })?;
+
+        let data = match &crawl_response.data {
+            Some(data) if crawl_response.success => data,
+            _ => {
+                return Err(error_response(
+                    StatusCode::BAD_GATEWAY,
+                    format!("Crawl returned no page for {}", search_url),
+                ))
+            }
+        };
+
+        // Parse the response
+        let (markdown, html) = (data.markdown.as_deref(), data.html.as_deref());
+        let mut listing = parse_page(markdown, html, search_url, parser_mode);
+        if listing.is_empty() && !is_results_page(markdown, html, parser_mode) {
+            return Err(error_response(
+                StatusCode::BAD_GATEWAY,
+                format!("Crawl of {} has no results page for the {:?} parser", search_url, parser_mode),
+            ));
+        }
+
+        // Record the listing; a storage failure shouldn't cost the caller their results
+        if let Some(inventory) = &state.inventory {
+            if let Err(e) = inventory.record_listing(search_url, &mut listing, seen_at) {
+                println!("Failed to record inventory for {}: {}", search_url, e);
+            }
+        }
+        if let Some(stores) = &state.stores {
+            update_store_directory(stores, data, &listing, seen_at);
         }
+
+        vehicles.extend(listing.into_iter().filter(|item| seen_keys.insert(vehicle_key(item))));
+    }
+
+    // Other yards within the radius were recorded above but aren't what was asked for
+    if let Some(location) = &store {
+        retain_store(&mut vehicles, location);
+    }
+
+    // The site's distances are measured from whichever zip each URL searched, so
+    // measure again from one origin before sorting or filtering merged results
+    let origin = match &store {
+        Some(location) if request.zip_code.trim().is_empty() => location.zip.as_str(),
+        _ => request.zip_code.as_str(),
+    };
+    let mut warnings = Vec::new();
+    if !annotate_distances(&mut vehicles, origin, &state.zip_centroids) {
+        warnings.push(format!(
+            "{}, so distances are the site's own, each measured from the zip its page searched",
+            unlocated_zip(origin)
+        ));
+    }
+    filter_and_sort(&mut vehicles, request.max_miles, request.sort);
+
+    Ok(SearchResults { vehicles, warnings })
+}
+
+/// Finds the yard a search is limited to in the store directory.
+fn resolve_store(state: &AppState, id: &str) -> Result<StoreLocation, ApiError> {
+    let stores = store_directory(state)?;
+    if let Some(location) = stores.get(id).map_err(storage_error)? {
+        return Ok(location);
+    }
+
+    let known = stores.list(None).map_err(storage_error)?;
+    let suggestions = normalize::suggest(id, known.iter().map(|location| location.id.as_str()));
+    let message = if known.is_empty() {
+        "The store directory is empty until a search has been run".to_string()
     } else {
-        Vec::new()
+        format!("No store '{}', see GET /stores", id)
     };
+    let (status, Json(error)) = invalid_field("store_id", "not_found", message);
+    Err((status, Json(error.with_suggestions(suggestions))))
+}
 
-    let total_found = vehicles.len();
+/// Refreshes the store directory from the page's "Locations" list and learns
+/// store ids from the stores in the results.
+fn update_store_directory(
+    stores: &StoreDirectory,
+    data: &CrawlData,
+    listing: &[JunkyardItem],
+    seen_at: chrono::DateTime<chrono::Utc>,
+) {
+    let locations = parse_locations(data.markdown.as_deref(), data.html.as_deref());
+    let result = stores.upsert_locations(&locations, seen_at).and_then(|_| {
+        listing
+            .iter()
+            .filter_map(|item| item.store.as_ref())
+            .try_for_each(|store| stores.link_store(store))
+    });
+    if let Err(e) = result {
+        println!("Failed to update store directory: {}", e);
+    }
+}
 
-    Ok(Json(SearchResponse {
+/// Maps fetch failures to gateway-style statuses so clients can tell a busy or
+/// broken upstream apart from a bug in this service.
+fn fetch_error_status(error: &FetchError) -> StatusCode {
+    match error {
+        FetchError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        FetchError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        // Any other rejection by the upstream, e.g. Firecrawl's 402 when credits run out
+        FetchError::Unauthorized(_)
+        | FetchError::Upstream { .. }
+        | FetchError::RequestFailed(_)
+        | FetchError::ApiError(_) => StatusCode::BAD_GATEWAY,
+        FetchError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn scrape_formats_for(mode: ParserMode) -> Vec<ScrapeFormat> {
+    match mode {
+        ParserMode::Markdown => vec![ScrapeFormat::Markdown],
+        ParserMode::Html => vec![ScrapeFormat::Html],
+        ParserMode::Auto => vec![ScrapeFormat::Markdown, ScrapeFormat::Html],
+    }
+}
+
+// POST /parts/search - Find donor vehicles for a part
+pub async fn search_parts(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<PartSearchRequest>,
+) -> Result<Json<PartSearchResponse>, ApiError> {
+    perform_part_search(state, request).await
+}
+
+// GET /parts/search?part=<part>&zip_code=<zip>[&distance=<miles>] - Find donor vehicles for a part
+pub async fn search_parts_get(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<PartSearchResponse>, ApiError> {
+    let part = params
+        .get("part")
+        .ok_or_else(|| invalid_field("part", "required", "Missing 'part' parameter"))?;
+    let zip_code = params
+        .get("zip_code")
+        .ok_or_else(|| invalid_field("zip_code", "required", "Missing 'zip_code' parameter"))?;
+    let distance = match params.get("distance") {
+        Some(value) => Some(parse_distance_param(value)?),
+        None => None,
+    };
+    let parser = match params.get("parser") {
+        Some(value) => Some(
+            value
+                .parse::<ParserMode>()
+                .map_err(|error| invalid_field("parser", "unsupported_value", error))?,
+        ),
+        None => None,
+    };
+
+    let (sort, max_miles) = parse_sort_params(&params)?;
+
+    let request = PartSearchRequest {
+        part: part.clone(),
+        zip_code: zip_code.clone(),
+        distance,
+        parser,
+        sort,
+        max_miles,
+    };
+    perform_part_search(state, request).await
+}
+
+/// Expands a part into one search per fitting make/model/years and groups the
+/// results by part. Fitments shared between matching parts are only crawled once.
+async fn perform_part_search(
+    state: AppState,
+    request: PartSearchRequest,
+) -> Result<Json<PartSearchResponse>, ApiError> {
+    validate_part_search(&request).map_err(validation_error)?;
+
+    let parts: Vec<_> = state.interchange.find(&request.part).into_iter().cloned().collect();
+    if parts.is_empty() {
+        let suggestions = state.interchange.suggest(&request.part);
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(
+                ErrorResponse::new(format!("No interchange data for part: {}", request.part))
+                    .with_suggestions(suggestions),
+            ),
+        ));
+    }
+
+    let years = query_years(&request.part);
+    let fit_searches = |part: &Part| -> Vec<SearchRequest> {
+        part.fits
+            .iter()
+            .filter_map(|fit| fit.search_request(years, &request.zip_code, request.distance, request.parser))
+            .collect()
+    };
+    let mut searches: Vec<SearchRequest> = Vec::new();
+    for search in parts.iter().flat_map(fit_searches) {
+        if !searches.iter().any(|s| same_search(s, &search)) {
+            searches.push(search);
+        }
+    }
+
+    let (listings, mut warnings) = run_searches(&state, searches).await?;
+
+    let mut total_found = 0;
+    let part_results = parts
+        .into_iter()
+        .map(|part| {
+            let mut seen_keys = HashSet::new();
+            let mut vehicles: Vec<JunkyardItem> = fit_searches(&part)
+                .into_iter()
+                .filter_map(|search| listings.iter().find(|(s, _)| same_search(s, &search)))
+                .flat_map(|(_, results)| results.vehicles.iter().cloned())
+                .filter(|item| seen_keys.insert(vehicle_key(item)))
+                .collect();
+            filter_and_sort(&mut vehicles, request.max_miles, request.sort);
+            total_found += vehicles.len();
+            PartResult {
+                part,
+                total_found: vehicles.len(),
+                vehicles,
+            }
+        })
+        .collect();
+
+    add_search_warnings(&mut warnings, listings);
+
+    Ok(Json(PartSearchResponse {
         success: true,
-        vehicles,
-        search_params: request,
+        query: request.part,
+        parts: part_results,
         total_found,
+        warnings,
+    }))
+}
+
+/// Results of several searches, each paired with its request, and a warning for
+/// every search that failed.
+type SearchListings = (Vec<(SearchRequest, SearchResults)>, Vec<String>);
+
+/// Runs several searches a few at a time, pairing each with its vehicles. A failed
+/// search, e.g. for a donor model the catalog doesn't know, is left out with a
+/// warning; only when every search fails is that the response.
+async fn run_searches(state: &AppState, searches: Vec<SearchRequest>) -> Result<SearchListings, ApiError> {
+    let results: Vec<_> = stream::iter(searches.clone())
+        .map(|search| {
+            let state = state.clone();
+            async move { run_search(&state, &search).await }
+        })
+        .buffered(MAX_CONCURRENT_FETCHES)
+        .collect()
+        .await;
+    let mut listings = Vec::new();
+    let mut warnings = Vec::new();
+    let mut first_error = None;
+    for (search, result) in searches.into_iter().zip(results) {
+        match result {
+            Ok(results) => listings.push((search, results)),
+            Err((status, Json(error))) => {
+                warnings.push(format!("Left out {}: {}", search.label(), error.error));
+                first_error.get_or_insert((status, Json(error)));
+            }
+        }
+    }
+    match first_error {
+        Some(error) if listings.is_empty() => Err(error),
+        _ => Ok((listings, warnings)),
+    }
+}
+
+/// Adds the warnings of each search's results once; every search measures from
+/// the same zip, so they tend to repeat.
+fn add_search_warnings(warnings: &mut Vec<String>, listings: Vec<(SearchRequest, SearchResults)>) {
+    for warning in listings.into_iter().flat_map(|(_, results)| results.warnings) {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+}
+
+// POST /trips/plan - Plan a round trip through the yards that have the most wanted items
+pub async fn plan_trip(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<TripPlanRequest>,
+) -> Result<Json<TripPlanResponse>, ApiError> {
+    validate_trip(&request).map_err(validation_error)?;
+    let home = state.zip_centroids.locate(&request.zip_code).ok_or_else(|| {
+        invalid_field(
+            "zip_code",
+            "unknown_location",
+            format!("{}, so no trip can be planned from it", unlocated_zip(&request.zip_code)),
+        )
+    })?;
+
+    let distance = request.search_radius();
+
+    // A vehicle is one search; a part is one per vehicle it fits
+    let mut item_searches: Vec<Vec<SearchRequest>> = Vec::new();
+    for (index, item) in request.wanted.iter().enumerate() {
+        let searches = match &item.part {
+            Some(part) => {
+                let parts = state.interchange.find(part);
+                if parts.is_empty() {
+                    let suggestions = state.interchange.suggest(part);
+                    let (_, Json(error)) = invalid_field(
+                        &format!("wanted[{}].part", index),
+                        "not_found",
+                        format!("No interchange data for part: {}", part),
+                    );
+                    return Err((StatusCode::NOT_FOUND, Json(error.with_suggestions(suggestions))));
+                }
+                let years = query_years(part);
+                parts
+                    .iter()
+                    .flat_map(|part| &part.fits)
+                    .filter_map(|fit| fit.search_request(years, &request.zip_code, distance, request.parser))
+                    .collect()
+            }
+            None => vec![item.search_request(&request.zip_code, distance, request.parser)],
+        };
+        item_searches.push(searches);
+    }
+
+    let mut searches: Vec<SearchRequest> = Vec::new();
+    for search in item_searches.iter().flatten() {
+        if !searches.iter().any(|s| same_search(s, search)) {
+            searches.push(search.clone());
+        }
+    }
+    let (listings, mut warnings) = run_searches(&state, searches).await?;
+
+    let wanted: Vec<(String, Vec<JunkyardItem>)> = request
+        .wanted
+        .iter()
+        .zip(&item_searches)
+        .map(|(item, searches)| {
+            let mut seen_keys = HashSet::new();
+            let vehicles = searches
+                .iter()
+                .filter_map(|search| listings.iter().find(|(s, _)| same_search(s, search)))
+                .flat_map(|(_, results)| results.vehicles.iter().cloned())
+                .filter(|vehicle| seen_keys.insert(vehicle_key(vehicle)))
+                .collect();
+            (item.label(), vehicles)
+        })
+        .collect();
+    let plan = trips::plan_route(home, &wanted, &state.zip_centroids, request.max_total_miles);
+    add_search_warnings(&mut warnings, listings);
+
+    Ok(Json(TripPlanResponse {
+        success: true,
+        zip_code: request.zip_code,
+        max_total_miles: request.max_total_miles,
+        plan,
+        warnings,
     }))
 }
 
+fn same_search(a: &SearchRequest, b: &SearchRequest) -> bool {
+    normalize::fold(&a.make) == normalize::fold(&b.make)
+        && a.models.iter().map(|m| normalize::fold(m)).eq(b.models.iter().map(|m| normalize::fold(m)))
+        && (a.year_min, a.year_max) == (b.year_min, b.year_max)
+}
+
 // GET /health - Health check endpoint
 pub async fn health_check() -> Json<serde_json::Value> {
     Json(serde_json::json!({
@@ -224,10 +737,7 @@ pub async fn get_supported_models(
         .ok_or_else(|| {
             (
                 StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    success: false,
-                    error: "Missing 'make' parameter".to_string(),
-                }),
+                Json(ErrorResponse::new("Missing 'make' parameter")),
             )
         })?;
 
@@ -238,4 +748,766 @@ pub async fn get_supported_models(
         "make": make,
         "models": models
     })))
-}
\ No newline at end of file
+}
+
+// GET /stores?state=<state> - Yards from the site's Locations list
+pub async fn list_stores(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let stores = store_directory(&state)?;
+    let locations = stores
+        .list(params.get("state").map(String::as_str))
+        .map_err(storage_error)?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "total_found": locations.len(),
+        "stores": locations
+    })))
+}
+
+// GET /stores/{id} - One yard, by slug ("newark") or Pick-n-Pull store id
+pub async fn get_store(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<StoreLocation>, ApiError> {
+    let stores = store_directory(&state)?;
+    let location = stores
+        .get(&id)
+        .map_err(storage_error)?
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, format!("No store with id {}", id)))?;
+    Ok(Json(location))
+}
+
+// GET /stores/{id}/inventory?since=<rfc3339> - Cars set in or removed from a yard, 7 days by default
+pub async fn get_store_inventory(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<StoreInventoryChanges>, ApiError> {
+    let inventory = inventory_store(&state)?;
+    let since = parse_since(&params)?.unwrap_or_else(|| chrono::Utc::now() - chrono::Duration::days(7));
+
+    // Vehicles are stored under the site's numeric store id; slugs go through the directory
+    let store_id = match id.trim().parse::<u32>() {
+        Ok(store_id) => store_id,
+        Err(_) => {
+            let location = resolve_store(&state, &id)?;
+            location.store_id.ok_or_else(|| {
+                error_response(
+                    StatusCode::NOT_FOUND,
+                    format!("No inventory for store {} until it shows up in search results", location.id),
+                )
+            })?
+        }
+    };
+
+    let changes = inventory.store_changes(store_id, since).map_err(storage_error)?;
+    Ok(Json(changes))
+}
+
+// GET /vehicles/{id}/history - When a vehicle was first seen, every sighting and when it disappeared
+pub async fn get_vehicle_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<VehicleHistory>, ApiError> {
+    let inventory = inventory_store(&state)?;
+    match inventory.vehicle_history(id.trim()).map_err(storage_error)? {
+        HistoryLookup::Found(history) => Ok(Json(*history)),
+        HistoryLookup::NotFound => Err(error_response(StatusCode::NOT_FOUND, format!("No vehicle with id {}", id))),
+        // The same parsed id at several yards; the caller picks one by its vehicle key
+        HistoryLookup::Ambiguous(keys) => Err((
+            StatusCode::CONFLICT,
+            Json(
+                ErrorResponse::new(format!("Vehicle id {} is listed by several stores, use a vehicle key", id))
+                    .with_code("ambiguous_vehicle")
+                    .with_suggestions(keys),
+            ),
+        )),
+    }
+}
+
+// POST /admin/catalog/reload - Re-read the make/model catalog without a restart
+pub async fn reload_catalog(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let catalog = state
+        .pick_n_pull
+        .reload_catalog()
+        .map_err(|e| match e {
+            catalog::Error::NotConfigured => (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(e.to_string()).with_code("no_catalog_file")),
+            ),
+            e => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "makes": catalog.makes.len(),
+        "models": catalog.model_count()
+    })))
+}
+
+/// Explains a zip code missing from the centroid data and how to add it.
+fn unlocated_zip(zip: &str) -> String {
+    format!(
+        "No coordinates for zip code {} in the zip centroid data (set ZIP_CENTROIDS_PATH for a fuller set)",
+        zip.trim()
+    )
+}
+
+// A body that isn't JSON at all
+fn json_rejection(rejection: JsonRejection) -> ApiError {
+    let code = match rejection {
+        JsonRejection::MissingJsonContentType(_) => "unsupported_media_type",
+        _ => "invalid_json",
+    };
+    invalid_field("body", code, rejection.body_text())
+}
+
+fn error_response(status: StatusCode, error: impl Into<String>) -> ApiError {
+    (
+        status,
+        Json(ErrorResponse::new(error)),
+    )
+}
+
+// Unknown make/model, with "did you mean" candidates
+fn lookup_error(error: LookupError) -> ApiError {
+    let suggestions = error.suggestions().to_vec();
+    (
+        StatusCode::BAD_REQUEST,
+        Json(
+            ErrorResponse::new(error.to_string())
+                .with_code("unsupported_vehicle")
+                .with_suggestions(suggestions),
+        ),
+    )
+}
+
+fn validation_error(field_errors: Vec<FieldError>) -> ApiError {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse::validation(field_errors)))
+}
+
+fn invalid_field(field: &str, code: &str, message: impl Into<String>) -> ApiError {
+    validation_error(vec![FieldError::new(field, code, message)])
+}
+
+/// Reads the optional `sort=distance|newest` and `max_miles=<miles>` parameters.
+fn parse_sort_params(params: &HashMap<String, String>) -> Result<(Option<SortOrder>, Option<f64>), ApiError> {
+    let sort = match params.get("sort") {
+        Some(value) => Some(value.parse::<SortOrder>()
+            .map_err(|error| invalid_field("sort", "unsupported_value", error))?),
+        None => None,
+    };
+    let max_miles = match params.get("max_miles") {
+        Some(value) => Some(value.trim().parse::<f64>().map_err(|_| {
+            invalid_field("max_miles", "invalid_format", format!("'{}' is not a number", value))
+        })?),
+        None => None,
+    };
+    Ok((sort, max_miles))
+}
+
+// Mileages between the site's radii are rounded up, see `SearchRadius`
+fn parse_distance_param(value: &str) -> Result<SearchRadius, ApiError> {
+    value
+        .parse::<SearchRadius>()
+        .map_err(|error| invalid_field("distance", "invalid_format", error))
+}
+
+fn parse_number_param(field: &str, value: &str) -> Result<u32, ApiError> {
+    value
+        .trim()
+        .parse::<u32>()
+        .map_err(|_| invalid_field(field, "invalid_format", format!("'{}' is not a number", value)))
+}
+
+fn watch_store(state: &AppState) -> Result<Arc<WatchStore>, ApiError> {
+    state.watches.clone().ok_or_else(|| {
+        error_response(StatusCode::SERVICE_UNAVAILABLE, "Watchlist storage is not configured")
+    })
+}
+
+fn inventory_store(state: &AppState) -> Result<Arc<InventoryStore>, ApiError> {
+    state.inventory.clone().ok_or_else(|| {
+        error_response(StatusCode::SERVICE_UNAVAILABLE, "Inventory storage is not configured")
+    })
+}
+
+fn store_directory(state: &AppState) -> Result<Arc<StoreDirectory>, ApiError> {
+    state.stores.clone().ok_or_else(|| {
+        error_response(StatusCode::SERVICE_UNAVAILABLE, "Store directory is not configured")
+    })
+}
+
+fn storage_error(e: impl std::fmt::Display) -> ApiError {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e))
+}
+
+fn find_watch(watches: &WatchStore, id: i64) -> Result<SavedSearch, ApiError> {
+    watches
+        .get(id)
+        .map_err(storage_error)?
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, format!("No watch with id {}", id)))
+}
+
+// POST /watches - Save a search to the watchlist
+pub async fn create_watch(
+    State(state): State<AppState>,
+    ValidJson(request): ValidJson<CreateWatchRequest>,
+) -> Result<(StatusCode, Json<SavedSearch>), ApiError> {
+    let watches = watch_store(&state)?;
+
+    let mut field_errors = Vec::new();
+    for (field, value) in [("name", &request.name), ("owner", &request.owner)] {
+        if value.trim().is_empty() {
+            field_errors.push(FieldError::new(field, "required", format!("'{}' must not be empty", field)));
+        }
+    }
+    if !field_errors.is_empty() {
+        return Err(validation_error(field_errors));
+    }
+
+    // Reject searches that could never run
+    validate_search(&request.search).map_err(validation_error)?;
+    if let Err(error) = state.pick_n_pull.generate_search_urls(
+        &request.search.make,
+        &request.search.models,
+        &request.search.zip_code,
+        request.search.distance.unwrap_or_default(),
+        (request.search.year_min, request.search.year_max),
+    ) {
+        return Err(lookup_error(error));
+    }
+
+    let saved = watches
+        .create(request.name.trim(), request.owner.trim(), &request.search)
+        .map_err(storage_error)?;
+    Ok((StatusCode::CREATED, Json(saved)))
+}
+
+// GET /watches?owner=<owner> - List saved searches
+pub async fn list_watches(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let watches = watch_store(&state)?;
+    let list = watches
+        .list(params.get("owner").map(String::as_str))
+        .map_err(storage_error)?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "watches": list
+    })))
+}
+
+// GET /watches/{id} - Get one saved search
+pub async fn get_watch(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<SavedSearch>, ApiError> {
+    let watches = watch_store(&state)?;
+    Ok(Json(find_watch(&watches, id)?))
+}
+
+// DELETE /watches/{id} - Remove a saved search
+pub async fn delete_watch(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, ApiError> {
+    let watches = watch_store(&state)?;
+    if watches.delete(id).map_err(storage_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(error_response(StatusCode::NOT_FOUND, format!("No watch with id {}", id)))
+    }
+}
+
+// POST /watches/{id}/run - Run one saved search now
+pub async fn run_watch(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<WatchRunResult>, ApiError> {
+    let watches = watch_store(&state)?;
+    let watch = find_watch(&watches, id)?;
+    Ok(Json(execute_watch(&state, &watches, watch).await))
+}
+
+// POST /watches/run?owner=<owner> - Run every saved search (optionally one owner's)
+pub async fn run_all_watches(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let watches = watch_store(&state)?;
+    let list = watches
+        .list(params.get("owner").map(String::as_str))
+        .map_err(storage_error)?;
+
+    let mut results = Vec::with_capacity(list.len());
+    for watch in list {
+        results.push(execute_watch(&state, &watches, watch).await);
+    }
+
+    Ok(Json(serde_json::json!({
+        "success": results.iter().all(|r| r.success),
+        "results": results
+    })))
+}
+
+async fn execute_watch(state: &AppState, watches: &WatchStore, watch: SavedSearch) -> WatchRunResult {
+    match check_watch(state, watches, &watch).await {
+        Ok(check) => WatchRunResult {
+            watch_id: watch.id,
+            name: watch.name,
+            success: true,
+            total_found: check.vehicles.len(),
+            vehicles: check.vehicles,
+            new_arrivals: check.arrivals,
+            error: None,
+        },
+        Err(error) => WatchRunResult {
+            watch_id: watch.id,
+            name: watch.name,
+            success: false,
+            total_found: 0,
+            vehicles: Vec::new(),
+            new_arrivals: Vec::new(),
+            error: Some(error),
+        },
+    }
+}
+
+/// Reads a `year` query value: "2004", "2000-2006", or open-ended "2004-", "2004+", "-2006".
+fn parse_year_range(value: &str) -> Option<YearRange> {
+    let year = |s: &str| -> Option<Option<u32>> {
+        let s = s.trim();
+        if s.is_empty() {
+            Some(None)
+        } else {
+            s.parse::<u32>().ok().map(Some)
+        }
+    };
+    let value = value.trim();
+    let range = match (value.strip_suffix('+'), value.split_once('-')) {
+        (Some(from), _) => (year(from)?, None),
+        (None, Some((from, to))) => (year(from)?, year(to)?),
+        (None, None) => {
+            let year = year(value)?;
+            (year, year)
+        }
+    };
+    Some(range)
+}
+
+fn parse_since(params: &HashMap<String, String>) -> Result<Option<chrono::DateTime<chrono::Utc>>, ApiError> {
+    match params.get("since") {
+        Some(since) => chrono::DateTime::parse_from_rfc3339(since)
+            .map(|dt| Some(dt.with_timezone(&chrono::Utc)))
+            .map_err(|_| {
+                invalid_field(
+                    "since",
+                    "invalid_format",
+                    "'since' must be an RFC 3339 timestamp, e.g. 2025-04-01T00:00:00Z",
+                )
+            }),
+        None => Ok(None),
+    }
+}
+
+// GET /events?watch_id=<id>&since=<rfc3339> - New vehicle arrivals found by saved searches
+pub async fn list_events(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let watches = watch_store(&state)?;
+    let watch_id = match params.get("watch_id") {
+        Some(id) => Some(id.parse::<i64>().map_err(|_| {
+            invalid_field("watch_id", "invalid_format", format!("'{}' is not a saved search id", id))
+        })?),
+        None => None,
+    };
+    let since = parse_since(&params)?;
+
+    let events = watches.list_events(watch_id, since).map_err(storage_error)?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "events": events
+    })))
+}
+
+// GET /watches/{id}/events?since=<rfc3339> - New vehicle arrivals for one saved search
+pub async fn list_watch_events(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let watches = watch_store(&state)?;
+    let watch = find_watch(&watches, id)?;
+    let since = parse_since(&params)?;
+
+    let events = watches.list_events(Some(watch.id), since).map_err(storage_error)?;
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "watch": watch,
+        "events": events
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::StubFetcher;
+
+    fn impreza_search() -> SearchRequest {
+        serde_json::from_value(serde_json::json!({
+            "make": "Subaru", "model": "Impreza Wagon", "zip_code": "94560"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn missing_page_fails_without_marking_vehicles_gone() {
+        let search = impreza_search();
+        let url = PicknPullSearch::new()
+            .generate_search_urls(&search.make, &search.models, "94560", SearchRadius::Miles50, (None, None))
+            .unwrap()
+            .remove(0);
+        let fetcher = StubFetcher::with_pages([(url, include_str!("../firecrawl_sample_output.md").to_string())]);
+        let mut state = AppState::new(Arc::new(fetcher), PicknPullSearch::new())
+            .with_inventory(InventoryStore::open_in_memory().unwrap());
+        assert_eq!(run_search(&state, &search).await.unwrap().vehicles.len(), 1);
+
+        state.fetcher = Arc::new(StubFetcher::default());
+        let (status, _) = run_search(&state, &search).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        let inventory = state.inventory.as_ref().unwrap();
+        assert!(inventory.get_vehicle("47:2005_subaru_impreza_wagon_132").unwrap().unwrap().availability);
+
+        // Markdown from a fetcher that only returns HTML is just as unusable
+        assert!(!is_results_page(None, Some("<h2>Matching Vehicles</h2>"), ParserMode::Markdown));
+    }
+
+    #[tokio::test]
+    async fn merges_model_pages_without_duplicates() {
+        let mut search = impreza_search();
+        search.models = vec!["Impreza Wagon".to_string(), "Outback".to_string()];
+        let urls = PicknPullSearch::new()
+            .generate_search_urls(&search.make, &search.models, "94560", SearchRadius::Miles50, (None, None))
+            .unwrap();
+        assert_eq!(urls.len(), 2);
+
+        // The Outback page lists the Impreza again, as it would when a yard's row is
+        // shared by both models
+        let impreza_page = include_str!("../firecrawl_sample_output.md").to_string();
+        let impreza_row = "| 2005 | Subaru | Impreza Wagon | 132 | 04/02/2025 | 2005 Subaru Impreza Wagon<br>Row 132<br>Set: 04/02/2025 |";
+        let outback_row = "| ![2001 Subaru Outback](https://cdn.row52.com/images/outback.JPG) | 2001 | Subaru | Outback | 140 | 03/15/2025 | 2001 Subaru Outback<br>Row 140<br>Set: 03/15/2025 |";
+        let outback_page = impreza_page.replace(impreza_row, &format!("{}\n{}", impreza_row, outback_row));
+        let fetcher = StubFetcher::with_pages(urls.into_iter().zip([impreza_page, outback_page]));
+        let state = AppState::new(Arc::new(fetcher), PicknPullSearch::new());
+
+        let vehicles = run_search(&state, &search).await.unwrap().vehicles;
+        let keys: Vec<_> = vehicles.iter().map(vehicle_key).collect();
+        assert_eq!(keys, ["47:2005_subaru_impreza_wagon_132", "47:2001_subaru_outback_140"]);
+    }
+
+    #[tokio::test]
+    async fn store_search_keeps_only_that_yard() {
+        let sample = include_str!("../firecrawl_sample_output.md");
+        let stores = StoreDirectory::open_in_memory().unwrap();
+        stores
+            .upsert_locations(&parse_locations(Some(sample), None), chrono::Utc::now())
+            .unwrap();
+        let oakland = stores.get("oakland").unwrap().unwrap();
+        assert_eq!(oakland.store_id, None);
+
+        // The 10 mile search around Oakland's zip also reaches Newark
+        let oakland_rows = "[Pick-n-Pull - Oakland](https://www.picknpull.com/locations/41/oakland-ca)(Approx. 3.1 miles)\n\n\
+            8225 Baldwin Street •  Oakland, CA 94621[510-632-4544](tel:510-632-4544)Store Layout Map\\|Part Pricing\n\n\
+            | Photo | Year | Make | Model | Row | Set Date |\n| --- | --- | --- | --- | --- | --- |\n\
+            | ![2003 Subaru Impreza Wagon](https://cdn.row52.com/images/b.JPG) | 2003 | Subaru | Impreza Wagon | 18 | 03/28/2025 | 2003 Subaru Impreza Wagon<br>Row 18<br>Set: 03/28/2025 |\n\n\
+            Displaying 2 vehicles";
+        let page = sample.replace("Displaying 1 vehicles", oakland_rows);
+        let search = SearchRequest {
+            zip_code: String::new(),
+            store_id: Some("oakland".to_string()),
+            ..impreza_search()
+        };
+        let url = PicknPullSearch::new()
+            .generate_search_urls(&search.make, &search.models, "94621", SearchRadius::Miles10, (None, None))
+            .unwrap()
+            .remove(0);
+        let state = AppState::new(Arc::new(StubFetcher::with_pages([(url, page)])), PicknPullSearch::new())
+            .with_stores(stores);
+
+        let vehicles = run_search(&state, &search).await.unwrap().vehicles;
+        let keys: Vec<_> = vehicles.iter().map(vehicle_key).collect();
+        assert_eq!(keys, ["41:2003_subaru_impreza_wagon_18"]);
+        let stores = state.stores.as_ref().unwrap();
+        assert_eq!(stores.get("oakland").unwrap().unwrap().store_id, Some(41));
+
+        let search = SearchRequest { store_id: Some("oaklnd".to_string()), ..search };
+        let (status, Json(error)) = run_search(&state, &search).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error.suggestions, ["oakland"]);
+    }
+
+    #[tokio::test]
+    async fn warns_when_the_origin_cant_be_located() {
+        let search = SearchRequest { zip_code: "00000".to_string(), ..impreza_search() };
+        assert!(ZipCentroids::bundled().locate(&search.zip_code).is_none());
+        let url = PicknPullSearch::new()
+            .generate_search_urls(&search.make, &search.models, "00000", SearchRadius::Miles50, (None, None))
+            .unwrap()
+            .remove(0);
+        let fetcher = StubFetcher::with_pages([(url, include_str!("../firecrawl_sample_output.md").to_string())]);
+        let state = AppState::new(Arc::new(fetcher), PicknPullSearch::new());
+
+        let SearchResults { vehicles, warnings } = run_search(&state, &search).await.unwrap();
+        let store = vehicles[0].store.as_ref().unwrap();
+        assert_eq!((store.geo_distance_miles, store.distance_miles), (None, Some(14.8)));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("No coordinates for zip code 00000"), "{}", warnings[0]);
+    }
+
+    #[tokio::test]
+    async fn looks_up_makes_missing_from_the_catalog() {
+        let search_url = PicknPullSearch::new().base_url().to_string();
+        let form = |options: &str| {
+            format!(
+                r#"<select name="make"><option value="">Choose Make…</option>
+                   <option value="901">Mazda</option><option value="226">Subaru</option></select>
+                   <select name="model"><option value="">Choose Model…</option>{}</select>"#,
+                options
+            )
+        };
+        let results_url =
+            PicknPullSearch::new().generate_url(901, 9001, "94560", SearchRadius::Miles50, (None, None));
+        let results = include_str!("../firecrawl_sample_output.md").to_string();
+        let miata = form(r#"<option value="9001">Miata</option>"#);
+        let fetcher = StubFetcher::with_pages([(results_url, results)])
+            .with_html_page(&search_url, form(""))
+            .with_html_page(format!("{}?make=901", search_url), miata);
+        let state = AppState::new(Arc::new(fetcher), PicknPullSearch::new());
+
+        let search = SearchRequest {
+            make: "Mazda".to_string(),
+            models: vec!["Miata".to_string()],
+            ..impreza_search()
+        };
+        assert_eq!(run_search(&state, &search).await.unwrap().vehicles.len(), 1);
+        assert_eq!(state.pick_n_pull.resolve("mazda", "miata").unwrap(), (901, 9001));
+
+        // A make the site doesn't list is still refused, and only asked about once
+        let search = SearchRequest { make: "Yugo".to_string(), ..search };
+        for _ in 0..2 {
+            let (status, Json(error)) = run_search(&state, &search).await.unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(error.code.as_deref(), Some("unsupported_vehicle"));
+        }
+        assert!(!state.pick_n_pull.claim_site_lookup("yugo"));
+    }
+
+    #[tokio::test]
+    async fn part_search_keeps_the_searches_that_worked() {
+        let fit = |model: &str, year_min, year_max| crate::interchange::Fitment {
+            make: "Subaru".to_string(),
+            model: model.to_string(),
+            year_min,
+            year_max,
+        };
+        let interchange = Interchange {
+            parts: vec![Part {
+                id: "wagon-hatch".to_string(),
+                name: "Rear hatch".to_string(),
+                aliases: Vec::new(),
+                fits: vec![
+                    fit("Impreza Wagon", 2002, 2007),
+                    fit("Baja", 2003, 2006),
+                    fit("Legacy", 1995, 1999),
+                ],
+            }],
+        };
+        let wagon = ["Impreza Wagon".to_string()];
+        let url = PicknPullSearch::new()
+            .generate_search_urls("Subaru", &wagon, "94560", SearchRadius::Miles50, (Some(2005), Some(2005)))
+            .unwrap()
+            .remove(0);
+        let results = include_str!("../firecrawl_sample_output.md").to_string();
+        let fetcher = StubFetcher::with_pages([(url, results)]);
+        let part_search = |part: &str| -> PartSearchRequest {
+            serde_json::from_value(serde_json::json!({ "part": part, "zip_code": "94560" })).unwrap()
+        };
+        let state = AppState::new(Arc::new(fetcher), PicknPullSearch::new()).with_interchange(interchange);
+
+        // Only the 2005 wagon is searched: the Legacy doesn't fit 2005 and the Baja isn't in the catalog
+        let Json(response) = perform_part_search(state.clone(), part_search("hatch 2005")).await.unwrap();
+        assert_eq!(response.total_found, 1);
+        assert_eq!(response.warnings.len(), 1);
+        let warning = &response.warnings[0];
+        assert!(warning.starts_with("Left out Subaru Baja 2005: Unsupported model"), "{}", warning);
+
+        // With nothing to show, the failure is the response
+        let (status, _) = perform_part_search(state, part_search("hatch 2004")).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+    }
+
+    #[test]
+    fn accepts_one_model_or_many() {
+        let models = |body: serde_json::Value| serde_json::from_value::<SearchRequest>(body).unwrap().models;
+        assert_eq!(models(serde_json::json!({"make": "Subaru", "model": "Baja"})), ["Baja"]);
+        assert_eq!(
+            models(serde_json::json!({"make": "Subaru", "models": ["Baja", "Brat"]})),
+            ["Baja", "Brat"]
+        );
+        assert!(models(serde_json::json!({"make": "Subaru", "model": null})).is_empty());
+        assert!(models(serde_json::json!({"make": "Subaru"})).is_empty());
+        assert!(serde_json::from_value::<SearchRequest>(serde_json::json!({"make": "Subaru", "model": 7})).is_err());
+    }
+
+    async fn send(state: AppState, request: axum::http::Request<axum::body::Body>) -> (StatusCode, serde_json::Value) {
+        use tower::ServiceExt;
+
+        let response = create_app(state).oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn get(state: AppState, uri: &str) -> (StatusCode, serde_json::Value) {
+        send(state, axum::http::Request::get(uri).body(axum::body::Body::empty()).unwrap()).await
+    }
+
+    /// Sends `body` to `uri` and returns the status and the `field_errors` as
+    /// `(field, code)` pairs.
+    async fn post(state: AppState, uri: &str, body: &str) -> (StatusCode, Vec<(String, String)>) {
+        let request = axum::http::Request::post(uri)
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap();
+        let (status, body) = send(state, request).await;
+        let field_errors = body["field_errors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|e| (e["field"].as_str().unwrap().to_string(), e["code"].as_str().unwrap().to_string()))
+            .collect();
+        (status, field_errors)
+    }
+
+    #[tokio::test]
+    async fn reports_unreadable_bodies_as_field_errors() {
+        let state = AppState::new(Arc::new(StubFetcher::default()), PicknPullSearch::new())
+            .with_watches(WatchStore::open_in_memory().unwrap());
+        let field_error = |field: &str, code: &str| vec![(field.to_string(), code.to_string())];
+
+        let (status, errors) = post(state.clone(), "/search", r#"{"zip_code": "94560"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(errors, field_error("make", "required"));
+
+        let (status, errors) = post(state.clone(), "/search", r#"{"make": "Subaru", "zip_code": 94560"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(errors, field_error("body", "invalid_json"));
+
+        let body = r#"{"zip_code": "94560", "wanted": [{"make": "Subaru", "year_min": "old"}]}"#;
+        let (status, errors) = post(state.clone(), "/trips/plan", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(errors, field_error("wanted[0].year_min", "invalid_format"));
+
+        let body = r#"{"name": " ", "owner": "", "search": {"make": "Subaru", "zip_code": "94560"}}"#;
+        let (status, errors) = post(state, "/watches", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(errors, [field_error("name", "required"), field_error("owner", "required")].concat());
+    }
+
+    #[tokio::test]
+    async fn rejects_unusable_distances_by_field() {
+        let state = AppState::new(Arc::new(StubFetcher::default()), PicknPullSearch::new());
+        for distance in ["0", "\"far\"", "-10", "600"] {
+            let body = format!(r#"{{"make": "Subaru", "zip_code": "94560", "distance": {}}}"#, distance);
+            let (status, errors) = post(state.clone(), "/search", &body).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", distance);
+            assert_eq!(errors, [("distance".to_string(), "invalid_format".to_string())], "{}", distance);
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_vehicle_history_and_store_inventory() {
+        let inventory = InventoryStore::open_in_memory().unwrap();
+        let url = "https://www.picknpull.com/check-inventory/vehicle-search?make=226&model=4154";
+        let newark = parse_page(Some(include_str!("../firecrawl_sample_output.md")), None, url, ParserMode::Markdown);
+        let mut oakland = newark.clone();
+        oakland[0].store.as_mut().unwrap().store_id = Some(41);
+        let seen_at = "2025-04-10T00:00:00Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap();
+        inventory.record_listing(url, &mut [newark, oakland].concat(), seen_at).unwrap();
+        inventory.record_listing(url, &mut [], seen_at + chrono::Duration::days(1)).unwrap();
+        let state = AppState::new(Arc::new(StubFetcher::default()), PicknPullSearch::new()).with_inventory(inventory);
+
+        let (status, body) = get(state.clone(), "/vehicles/2005_subaru_impreza_wagon_132/history").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["code"], "ambiguous_vehicle");
+        assert_eq!(
+            body["suggestions"],
+            serde_json::json!(["41:2005_subaru_impreza_wagon_132", "47:2005_subaru_impreza_wagon_132"])
+        );
+
+        let (status, body) = get(state.clone(), "/vehicles/47:2005_subaru_impreza_wagon_132/history").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["days_in_yard"], 9.0);
+        let (status, _) = get(state.clone(), "/vehicles/1999_subaru_baja_1/history").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = get(state, "/stores/47/inventory?since=2025-04-01T00:00:00Z").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["set"].as_array().unwrap().len(), 1);
+        assert_eq!(body["removed"].as_array().unwrap().len(), 1);
+        assert_eq!(body["average_days_in_yard"], 9.0);
+    }
+
+    #[tokio::test]
+    async fn reports_bad_event_filters_by_field() {
+        let state = AppState::new(Arc::new(StubFetcher::default()), PicknPullSearch::new())
+            .with_watches(WatchStore::open_in_memory().unwrap());
+
+        for (uri, field) in [("/events?watch_id=wagons", "watch_id"), ("/events?since=2025-04-01", "since")] {
+            let (status, body) = get(state.clone(), uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+            assert_eq!(body["code"], "validation_failed");
+            assert_eq!(body["field_errors"][0]["field"], field);
+            assert_eq!(body["field_errors"][0]["code"], "invalid_format");
+        }
+        assert_eq!(get(state, "/events?watch_id=1").await.0, StatusCode::OK);
+    }
+
+    #[test]
+    fn parses_open_and_closed_year_ranges() {
+        assert_eq!(parse_year_range("2004"), Some((Some(2004), Some(2004))));
+        assert_eq!(parse_year_range(" 2002 - 2007 "), Some((Some(2002), Some(2007))));
+        assert_eq!(parse_year_range("2004-"), Some((Some(2004), None)));
+        assert_eq!(parse_year_range("2004+"), Some((Some(2004), None)));
+        assert_eq!(parse_year_range("-2006"), Some((None, Some(2006))));
+
+        assert_eq!(parse_year_range("newer"), None);
+        assert_eq!(parse_year_range("2004+2006"), None);
+        assert_eq!(parse_year_range("2000-2004-2008"), None);
+        assert_eq!(parse_year_range("-2006+"), None);
+    }
+
+    #[test]
+    fn maps_upstream_rejections_to_gateway_statuses() {
+        let status = |code: u16| {
+            let code = reqwest::StatusCode::from_u16(code).unwrap();
+            fetch_error_status(&FetchError::from_status(code, None, String::new()))
+        };
+        assert_eq!(status(402), StatusCode::BAD_GATEWAY);
+        assert_eq!(status(404), StatusCode::BAD_GATEWAY);
+        assert_eq!(status(429), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(status(408), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(status(503), StatusCode::BAD_GATEWAY);
+        assert_eq!(
+            fetch_error_status(&FetchError::Config("no key".to_string())),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
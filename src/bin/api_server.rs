@@ -1,6 +1,18 @@
-use junkyardTracker::api::create_app;
-use junkyardTracker::firecrawl_client::FirecrawlClient;
+use junkyardTracker::api::{create_app, AppState};
+use junkyardTracker::data_file::DataFile;
+use junkyardTracker::email::{DigestMode, EmailNotifier};
+use junkyardTracker::fetcher;
+use junkyardTracker::geo::ZipCentroids;
+use junkyardTracker::interchange::Interchange;
+use junkyardTracker::inventory::InventoryStore;
+use junkyardTracker::pick_n_pull::PicknPullSearch;
+use junkyardTracker::scheduler;
+use junkyardTracker::stores::StoreDirectory;
+use junkyardTracker::watchlist::WatchStore;
+use junkyardTracker::webhook::WebhookNotifier;
 use std::env;
+use std::str::FromStr;
+use std::time::Duration;
 use tokio::net::TcpListener;
 
 #[tokio::main]
@@ -8,27 +20,130 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load .env file
     dotenv::dotenv().ok();
     
-    // Get API key from environment variable
-    let api_key = env::var("FIRECRAWL_API_KEY")
-        .expect("FIRECRAWL_API_KEY environment variable not set");
+    // Pick the page fetcher: Firecrawl (default) or a direct HTTP fetch
+    let backend = env::var("FETCH_BACKEND").unwrap_or_else(|_| "firecrawl".to_string());
+    let fetcher = fetcher::from_env(&backend)?;
 
-    // Create FirecrawlClient
-    let firecrawl_client = FirecrawlClient::new(api_key);
+    // Make/model ids come from CATALOG_PATH when set, otherwise the bundled catalog
+    let mut pick_n_pull = match env::var("CATALOG_PATH") {
+        Ok(path) => {
+            println!("📚 Loading catalog from {}", path);
+            PicknPullSearch::from_catalog_file(path)?
+        }
+        Err(_) => PicknPullSearch::new(),
+    };
+
+    // Allow pointing searches at another host, e.g. a local stand-in
+    if let Ok(base_url) = env::var("PICKNPULL_BASE_URL") {
+        pick_n_pull = pick_n_pull.with_base_url(base_url);
+    }
+
+    // Open the inventory database that tracks when vehicles come and go
+    let db_path = env::var("INVENTORY_DB_PATH").unwrap_or_else(|_| "junkyard_inventory.db".to_string());
+    let inventory = InventoryStore::open(&db_path)?;
+    let watches = WatchStore::open(&db_path)?;
+    let stores = StoreDirectory::open(&db_path)?;
+    println!("🗄️  Inventory database: {}", db_path);
 
     // Create the app with routes
-    let app = create_app(firecrawl_client);
+    let mut state = AppState::new(fetcher, pick_n_pull)
+        .with_inventory(inventory)
+        .with_watches(watches)
+        .with_stores(stores);
+
+    // Zip centroids for computed distances, bundled unless ZIP_CENTROIDS_PATH is set
+    if let Ok(path) = env::var("ZIP_CENTROIDS_PATH") {
+        let centroids = ZipCentroids::load(&path)?;
+        println!("🌎 Loaded {} zip centroids from {}", centroids.len(), path);
+        state = state.with_zip_centroids(centroids);
+    } else {
+        println!("🌎 Locating zip codes by sectional center; set ZIP_CENTROIDS_PATH for exact zip centroids");
+    }
+
+    // Part interchange data for /parts/search, bundled unless INTERCHANGE_PATH is set
+    if let Ok(path) = env::var("INTERCHANGE_PATH") {
+        println!("🔩 Loading interchange data from {}", path);
+        state = state.with_interchange(Interchange::load(path)?);
+    }
+
+    // POST new arrivals to a webhook, signed when WEBHOOK_SECRET is set
+    if let Ok(webhook_url) = env::var("WEBHOOK_URL") {
+        let mut webhook = WebhookNotifier::new(&webhook_url)?;
+        if let Ok(secret) = env::var("WEBHOOK_SECRET") {
+            webhook = webhook.with_secret(secret);
+        }
+        if let Some(retries) = parse_env("WEBHOOK_MAX_RETRIES")? {
+            webhook = webhook.with_max_retries(retries);
+        }
+        state = state.with_notifier(webhook);
+        println!("🔔 Sending new arrivals to webhook {}", webhook_url);
+    }
+
+    // Email digests of new arrivals when SMTP_HOST is set
+    let watches = state.watches.clone().expect("saved searches are enabled above");
+    if let Some(email) = EmailNotifier::from_env(watches)? {
+        if email.mode() == DigestMode::Daily {
+            email.spawn_daily_digest();
+        }
+        println!("📧 Emailing new arrivals ({:?} digest)", email.mode());
+        state = state.with_notifier(email);
+    }
+
+    // Re-run saved searches in the background; 0 turns the scheduler off
+    let interval_minutes = parse_env::<u64>("WATCH_INTERVAL_MINUTES")?.unwrap_or(360);
+    if interval_minutes > 0 {
+        scheduler::spawn(state.clone(), Duration::from_secs(interval_minutes * 60));
+        println!("⏰ Re-running saved searches every {} minutes", interval_minutes);
+    }
+
+    // Reload the catalog on SIGHUP, same as POST /admin/catalog/reload
+    #[cfg(unix)]
+    {
+        let pick_n_pull = state.pick_n_pull.clone();
+        let mut hangups = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                match pick_n_pull.reload_catalog() {
+                    Ok(catalog) => println!(
+                        "📚 Reloaded catalog: {} makes, {} models",
+                        catalog.makes.len(),
+                        catalog.model_count()
+                    ),
+                    Err(e) => println!("Catalog reload failed: {}", e),
+                }
+            }
+        });
+    }
+
+    let app = create_app(state);
 
     // Get port from environment or default to 3000
     let port = env::var("PORT").unwrap_or_else(|_| "3000".to_string());
     let addr = format!("0.0.0.0:{}", port);
     
-    println!("🚗 Junkyard Tracker API starting on http://{}", addr);
+    println!("🚗 Junkyard Tracker API starting on http://{} ({} fetcher)", addr, backend);
     println!("📋 Available endpoints:");
     println!("  POST /search - Search for vehicles");
-    println!("  GET  /search?make=<make>&model=<model>&year_min=<year>&year_max=<year>&zip_code=<zip> - Search for vehicles (GET)");
+    println!("  GET  /search?make=<make>[&model=<model>[,<model>...]][&year=<from>-<to>|&year_min=<year>&year_max=<year>](&zip_code=<zip>[&distance=<miles>|unlimited]|&store=<store>)[&sort=distance|newest][&max_miles=<miles>][&parser=auto|html|markdown] - Search for vehicles (GET)");
     println!("  GET  /health - Health check");
     println!("  GET  /supported-makes - Get supported makes");
     println!("  GET  /supported-models?make=<make> - Get supported models for a make");
+    println!("  POST /watches - Save a search (name, owner, search)");
+    println!("  GET  /watches?owner=<owner> - List saved searches");
+    println!("  GET  /watches/<id> - Get a saved search");
+    println!("  DELETE /watches/<id> - Delete a saved search");
+    println!("  POST /watches/<id>/run - Run a saved search now");
+    println!("  POST /watches/run?owner=<owner> - Run all saved searches");
+    println!("  GET  /watches/<id>/events?since=<time> - New arrivals for a saved search");
+    println!("  GET  /events?watch_id=<id>&since=<time> - New arrivals across saved searches");
+    println!("  GET  /stores?state=<state> - Yards seen in the site's Locations list");
+    println!("  GET  /stores/<id> - Get a yard by slug or store id");
+    println!("  GET  /stores/<id>/inventory?since=<time> - Cars set in or removed from a yard (last 7 days by default)");
+    println!("  GET  /vehicles/<id>/history - First seen, every sighting and disappearance of a vehicle");
+    println!("  POST /parts/search - Find donor vehicles for a part (part, zip_code, distance)");
+    println!("  GET  /parts/search?part=<part>&zip_code=<zip>[&distance=<miles>|unlimited][&sort=distance|newest][&max_miles=<miles>] - Find donor vehicles for a part (GET)");
+    println!("  POST /trips/plan - Plan a round trip to the yards with the most wanted vehicles/parts (zip_code, wanted, max_total_miles)");
+    println!("  POST /admin/catalog/reload - Reload the CATALOG_PATH make/model catalog (also on SIGHUP)");
 
     // Create listener and serve the app
     let listener = TcpListener::bind(&addr).await?;
@@ -36,3 +151,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// A numeric setting, or `None` when it isn't set; startup fails on anything else.
+fn parse_env<T: FromStr>(name: &str) -> Result<Option<T>, String> {
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid value for {}: '{}'", name, value)),
+        Err(_) => Ok(None),
+    }
+}
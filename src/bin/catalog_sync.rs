@@ -0,0 +1,55 @@
+use junkyardTracker::catalog::Catalog;
+use junkyardTracker::catalog_sync::sync_catalog;
+use junkyardTracker::data_file::DataFile;
+use junkyardTracker::fetcher;
+use junkyardTracker::pick_n_pull::PicknPullSearch;
+use std::env;
+use std::path::Path;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Load .env file
+    dotenv::dotenv().ok();
+
+    // Write to the first argument, else the catalog the API server loads. The
+    // bundled data/catalog.json is compiled in, so writing there needs a rebuild.
+    let catalog_path = env::var("CATALOG_PATH").ok();
+    let output = env::args()
+        .nth(1)
+        .or_else(|| catalog_path.clone())
+        .unwrap_or_else(|| "catalog.json".to_string());
+
+    // Same fetcher choice as the API server
+    let backend = env::var("FETCH_BACKEND").unwrap_or_else(|_| "firecrawl".to_string());
+    let fetcher = fetcher::from_env(&backend)?;
+
+    let mut pick_n_pull = PicknPullSearch::new();
+    if let Ok(base_url) = env::var("PICKNPULL_BASE_URL") {
+        pick_n_pull = pick_n_pull.with_base_url(base_url);
+    }
+
+    // Models for makes that fail to load are carried over from the existing catalog
+    let previous = if Path::new(&output).exists() {
+        Catalog::load(&output)?
+    } else {
+        Catalog::bundled()
+    };
+
+    println!("Syncing catalog from {} ({} fetcher)", pick_n_pull.base_url(), backend);
+    let catalog = sync_catalog(fetcher.as_ref(), pick_n_pull.base_url(), &previous).await?;
+    catalog.save(&output)?;
+
+    println!(
+        "Wrote {} makes and {} models to {}",
+        catalog.makes.len(),
+        catalog.model_count(),
+        output
+    );
+    if catalog_path.as_deref() == Some(output.as_str()) {
+        println!("Send SIGHUP or POST /admin/catalog/reload to a running API server to pick it up");
+    } else {
+        println!("Start the API server with CATALOG_PATH={} to use it", output);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
+use std::path::Path;
+
+use crate::data_file::{self, DataFile};
+
+/// Pick-n-Pull make and model ids, as used in the vehicle search URL.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Catalog {
+    pub makes: Vec<CatalogMake>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogMake {
+    pub name: String,
+    pub id: u32,
+    #[serde(default)]
+    pub models: Vec<CatalogModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogModel {
+    pub name: String,
+    pub id: u32,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    File(data_file::Error),
+    /// A reload was asked for, but the catalog is the one built into the binary
+    NotConfigured,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::File(e) => write!(f, "{}", e),
+            Error::NotConfigured => write!(
+                f,
+                "The catalog is built into the binary, set CATALOG_PATH to load one that can be reloaded"
+            ),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl From<data_file::Error> for Error {
+    fn from(error: data_file::Error) -> Self {
+        Error::File(error)
+    }
+}
+
+/// The bundled copy is `data/catalog.json`, as written by the `catalog_sync` binary.
+impl DataFile for Catalog {
+    const NAME: &'static str = "catalog";
+    const BUNDLED: &'static str = include_str!("../data/catalog.json");
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        data_file::parse_json(contents)
+    }
+}
+
+impl Catalog {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        Ok(data_file::save_json(self, path)?)
+    }
+
+    pub fn model_count(&self) -> usize {
+        self.makes.iter().map(|make| make.models.len()).sum()
+    }
+}
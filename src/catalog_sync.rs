@@ -0,0 +1,254 @@
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+use std::error::Error as StdError;
+
+use crate::catalog::{Catalog, CatalogMake, CatalogModel};
+use crate::fetcher::PageFetcher;
+use crate::firecrawl_client::{self, ScrapeFormat};
+use crate::normalize;
+
+#[derive(Debug)]
+pub enum Error {
+    Fetch(firecrawl_client::Error),
+    /// The search form came back without HTML or without a make dropdown
+    NoMakes(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Fetch(e) => write!(f, "Could not fetch search form: {}", e),
+            Error::NoMakes(url) => write!(f, "No make dropdown found at {}", url),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+/// Which dropdown of the vehicle search form to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dropdown {
+    Make,
+    Model,
+}
+
+impl Dropdown {
+    fn label(self) -> &'static str {
+        match self {
+            Dropdown::Make => "make",
+            Dropdown::Model => "model",
+        }
+    }
+}
+
+/// Reads the `(name, id)` options of the make or model `<select>` on the search form.
+///
+/// The markdown output only has the option labels run together ("MakeChoose
+/// MakeAcuraAlfa Romeo…"), so the ids have to come from the HTML `value`s.
+pub fn parse_dropdown(html: &str, dropdown: Dropdown) -> Vec<(String, u32)> {
+    let document = Html::parse_document(html);
+    let select_selector = Selector::parse("select").unwrap();
+    let option_selector = Selector::parse("option").unwrap();
+    let label = dropdown.label();
+
+    let Some(select) = document
+        .select(&select_selector)
+        .find(|select| is_dropdown(select, &option_selector, label))
+    else {
+        return Vec::new();
+    };
+
+    select
+        .select(&option_selector)
+        .filter_map(|option| {
+            let id = option.value().attr("value")?.trim().parse::<u32>().ok()?;
+            let name = option.text().collect::<String>().trim().to_string();
+            if name.is_empty() {
+                None
+            } else {
+                Some((name, id))
+            }
+        })
+        .collect()
+}
+
+// A dropdown is identified by its name/id attribute, or by the "Choose Make…" placeholder
+fn is_dropdown(select: &ElementRef, option_selector: &Selector, label: &str) -> bool {
+    let attrs_match = ["name", "id"].iter().any(|attr| {
+        select
+            .value()
+            .attr(attr)
+            .is_some_and(|value| value.to_lowercase().ends_with(label))
+    });
+    attrs_match
+        || select.select(option_selector).next().is_some_and(|option| {
+            option
+                .text()
+                .collect::<String>()
+                .to_lowercase()
+                .starts_with(&format!("choose {}", label))
+        })
+}
+
+/// Crawls the vehicle search form at `search_url` and builds a catalog of every make
+/// and its models.
+///
+/// The model dropdown is only filled in once a make is picked, so the form is
+/// fetched again with `?make=<id>` for each make. When that fails or lists no
+/// models, the make keeps the models it had in `previous`.
+pub async fn sync_catalog(
+    fetcher: &dyn PageFetcher,
+    search_url: &str,
+    previous: &Catalog,
+) -> Result<Catalog, Error> {
+    let html = fetch_form(fetcher, search_url).await.map_err(Error::Fetch)?;
+    let makes = parse_dropdown(&html, Dropdown::Make);
+    if makes.is_empty() {
+        return Err(Error::NoMakes(search_url.to_string()));
+    }
+    println!("Found {} makes, fetching models...", makes.len());
+
+    let mut catalog = Catalog::default();
+    for (name, id) in makes {
+        let make_url = format!("{}?make={}", search_url, id);
+        let fetched = fetch_form(fetcher, &make_url)
+            .await
+            .map(|html| parse_dropdown(&html, Dropdown::Model));
+        let models = match fetched {
+            Ok(models) if !models.is_empty() => {
+                models.into_iter().map(|(name, id)| CatalogModel { name, id }).collect()
+            }
+            // An empty dropdown most likely means the page fills it in with script
+            result => {
+                let reason = match result {
+                    Err(e) => e.to_string(),
+                    Ok(_) => "the model dropdown is empty".to_string(),
+                };
+                println!("No models for {} ({}), keeping previous: {}", name, id, reason);
+                previous
+                    .makes
+                    .iter()
+                    .find(|make| make.id == id)
+                    .map(|make| make.models.clone())
+                    .unwrap_or_default()
+            }
+        };
+        catalog.makes.push(CatalogMake { name, id, models });
+    }
+
+    Ok(catalog)
+}
+
+/// Finds `make` in the search form's make dropdown and reads its models, for a make
+/// the catalog is missing or has no models for. `None` when the site doesn't list
+/// the make either.
+pub async fn sync_make(
+    fetcher: &dyn PageFetcher,
+    search_url: &str,
+    make: &str,
+) -> Result<Option<CatalogMake>, Error> {
+    let html = fetch_form(fetcher, search_url).await.map_err(Error::Fetch)?;
+    let makes: HashMap<String, (String, u32)> = parse_dropdown(&html, Dropdown::Make)
+        .into_iter()
+        .map(|(name, id)| (normalize::fold(&name), (name, id)))
+        .collect();
+    if makes.is_empty() {
+        return Err(Error::NoMakes(search_url.to_string()));
+    }
+    let Some((name, id)) = normalize::resolve_make(&makes, make).cloned() else {
+        return Ok(None);
+    };
+
+    let html = fetch_form(fetcher, &format!("{}?make={}", search_url, id))
+        .await
+        .map_err(Error::Fetch)?;
+    let models = parse_dropdown(&html, Dropdown::Model)
+        .into_iter()
+        .map(|(name, id)| CatalogModel { name, id })
+        .collect();
+    Ok(Some(CatalogMake { name, id, models }))
+}
+
+async fn fetch_form(fetcher: &dyn PageFetcher, url: &str) -> Result<String, firecrawl_client::Error> {
+    let response = fetcher.fetch_page(url, &[ScrapeFormat::Html]).await?;
+    Ok(response.data.and_then(|data| data.html).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::firecrawl_client::{CrawlData, CrawlResponse};
+    use async_trait::async_trait;
+
+    const FORM: &str = r#"
+        <form>
+          <select name="make">
+            <option value="">Choose Make…</option>
+            <option value="2">Acura</option>
+            <option value="159">Mazda</option>
+            <option value="226">Subaru</option>
+          </select>
+          <select name="model">
+            <option value="">Choose Model…</option>
+            {models}
+          </select>
+        </form>"#;
+
+    struct FormFetcher;
+
+    #[async_trait]
+    impl PageFetcher for FormFetcher {
+        async fn fetch_page(&self, url: &str, _formats: &[ScrapeFormat]) -> Result<CrawlResponse, firecrawl_client::Error> {
+            let models = if url.ends_with("make=226") {
+                r#"<option value="4153">Impreza</option><option value="4154">Impreza Wagon</option>"#
+            } else if url.ends_with("make=2") {
+                return Err(firecrawl_client::Error::Timeout);
+            } else {
+                ""
+            };
+            Ok(CrawlResponse {
+                success: true,
+                data: Some(CrawlData {
+                    markdown: None,
+                    html: Some(FORM.replace("{models}", models)),
+                    url: Some(url.to_string()),
+                }),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn syncs_makes_and_models_from_form() {
+        let make = |name: &str, id, model: &str, model_id| CatalogMake {
+            name: name.to_string(),
+            id,
+            models: vec![CatalogModel { name: model.to_string(), id: model_id }],
+        };
+        let previous = Catalog {
+            makes: vec![make("Acura", 2, "Integra", 7), make("Mazda", 159, "Miata", 2410)],
+        };
+
+        let catalog = sync_catalog(&FormFetcher, "https://example.test/vehicle-search", &previous)
+            .await
+            .unwrap();
+
+        assert_eq!(catalog.makes.len(), 3);
+        // One make failed to load, the other loaded with an empty dropdown
+        assert_eq!(catalog.makes[0].models[0].name, "Integra");
+        assert_eq!(catalog.makes[1].models[0].name, "Miata");
+        let subaru = &catalog.makes[2];
+        assert_eq!((subaru.name.as_str(), subaru.id), ("Subaru", 226));
+        let models: Vec<_> = subaru.models.iter().map(|m| (m.name.as_str(), m.id)).collect();
+        assert_eq!(models, vec![("Impreza", 4153), ("Impreza Wagon", 4154)]);
+    }
+
+    #[tokio::test]
+    async fn syncs_one_make_on_demand() {
+        let url = "https://example.test/vehicle-search";
+        let subaru = sync_make(&FormFetcher, url, "subaru").await.unwrap().unwrap();
+        assert_eq!((subaru.name.as_str(), subaru.id, subaru.models.len()), ("Subaru", 226, 2));
+
+        assert!(sync_make(&FormFetcher, url, "Yugo").await.unwrap().is_none());
+        assert!(matches!(sync_make(&FormFetcher, url, "Acura").await, Err(Error::Fetch(_))));
+    }
+}
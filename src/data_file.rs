@@ -0,0 +1,75 @@
+use serde::Serialize;
+use std::error::Error as StdError;
+use std::fs;
+use std::path::Path;
+
+/// Reference data the binary is built with a copy of, which a file on disk can
+/// replace: the make/model catalog, part interchange and zip centroids.
+pub trait DataFile: Sized {
+    /// What the file holds, for messages ("catalog", "zip centroids").
+    const NAME: &'static str;
+    /// The copy built into the binary.
+    const BUNDLED: &'static str;
+
+    /// Reads the file's contents; the error says what's wrong with them.
+    fn parse(contents: &str) -> Result<Self, String>;
+
+    fn bundled() -> Self {
+        Self::parse(Self::BUNDLED).unwrap_or_else(|reason| panic!("bundled {} is invalid: {}", Self::NAME, reason))
+    }
+
+    fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path).map_err(|e| Error::Io(Self::NAME, e))?;
+        Self::parse(&contents).map_err(|reason| Error::Parse(Self::NAME, reason))
+    }
+}
+
+/// Why a data file couldn't be loaded or saved; each variant names the file's kind.
+#[derive(Debug)]
+pub enum Error {
+    Io(&'static str, std::io::Error),
+    Parse(&'static str, String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(name, e) => write!(f, "Could not read or write {}: {}", name, e),
+            Error::Parse(name, reason) => write!(f, "Invalid {}: {}", name, reason),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+/// `parse` for the JSON data files.
+pub fn parse_json<T: serde::de::DeserializeOwned>(contents: &str) -> Result<T, String> {
+    serde_json::from_str(contents).map_err(|e| e.to_string())
+}
+
+/// Writes a JSON data file in the layout `parse_json` reads.
+pub fn save_json<T: DataFile + Serialize>(data: &T, path: impl AsRef<Path>) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(data).map_err(|e| Error::Parse(T::NAME, e.to_string()))?;
+    fs::write(path, contents).map_err(|e| Error::Io(T::NAME, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::geo::ZipCentroids;
+
+    #[test]
+    fn names_the_file_kind_in_errors() {
+        let path = std::env::temp_dir().join(format!("data_file_test_{}.csv", std::process::id()));
+        fs::write(&path, "postal_code,latitude,longitude\n94560,37.5,north\n").unwrap();
+        let error = ZipCentroids::load(&path).unwrap_err();
+        assert_eq!(error.to_string(), "Invalid zip centroids: line 2: 94560,37.5,north");
+
+        let error = Catalog::load(&path).unwrap_err();
+        assert!(error.to_string().starts_with("Invalid catalog: "), "{}", error);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(Catalog::load(&path), Err(Error::Io("catalog", _))));
+    }
+}
@@ -0,0 +1,511 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Local, NaiveTime, TimeZone};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error as StdError;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::inventory;
+use crate::models::{ArrivalEvent, JunkyardItem};
+use crate::notifier::{Notifier, NotifyError};
+use crate::watchlist::WatchStore;
+
+/// Name the email notifier's progress through the arrival events is saved under.
+const CURSOR: &str = "email";
+
+#[derive(Debug)]
+pub enum Error {
+    Config(String),
+    Message(String),
+    Smtp(lettre::transport::smtp::Error),
+    Storage(inventory::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Config(msg) => write!(f, "Email configuration error: {}", msg),
+            Error::Message(msg) => write!(f, "Could not build email: {}", msg),
+            Error::Smtp(e) => write!(f, "SMTP error: {}", e),
+            Error::Storage(e) => write!(f, "Could not read or save sent arrivals: {}", e),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+/// When new arrivals are emailed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestMode {
+    /// An email as soon as a watch run finds arrivals
+    Immediate,
+    /// One email a day, sent by [`EmailNotifier::spawn_daily_digest`]
+    Daily,
+}
+
+/// How the SMTP connection is secured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    StartTls,
+    Tls,
+    /// Plain text, for local sinks and relays only
+    None,
+}
+
+/// Emails digests of new arrivals as plain text plus HTML.
+///
+/// Each digest holds every arrival recorded in the watch store since the last
+/// one was sent, so arrivals a failed send or a restart left behind go out with
+/// the next.
+#[derive(Clone)]
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    mode: DigestMode,
+    digest_at: NaiveTime,
+    watches: Arc<WatchStore>,
+    /// Held while a digest is sent, so two sends can't email the same arrivals
+    sending: Arc<Mutex<()>>,
+}
+
+impl EmailNotifier {
+    pub fn new(
+        host: &str,
+        port: u16,
+        security: SmtpSecurity,
+        credentials: Option<(String, String)>,
+        from: &str,
+        to: &[String],
+        watches: Arc<WatchStore>,
+    ) -> Result<Self, Error> {
+        let builder = match security {
+            SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .map_err(Error::Smtp)?,
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(Error::Smtp)?,
+            SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        };
+        let mut builder = builder.port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        let from = from
+            .parse::<Mailbox>()
+            .map_err(|e| Error::Config(format!("Invalid sender '{}': {}", from, e)))?;
+        let to = to
+            .iter()
+            .map(|addr| {
+                addr.trim()
+                    .parse::<Mailbox>()
+                    .map_err(|e| Error::Config(format!("Invalid recipient '{}': {}", addr, e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if to.is_empty() {
+            return Err(Error::Config("At least one recipient is required".to_string()));
+        }
+
+        // Start from the arrivals recorded so far the first time email is set up
+        watches.notifier_cursor(CURSOR).map_err(Error::Storage)?;
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            to,
+            mode: DigestMode::Immediate,
+            digest_at: NaiveTime::MIN,
+            watches,
+            sending: Arc::default(),
+        })
+    }
+
+    /// Send one digest a day at `at`, server local time, instead of one per run.
+    pub fn with_daily_digest(mut self, at: NaiveTime) -> Self {
+        self.mode = DigestMode::Daily;
+        self.digest_at = at;
+        self
+    }
+
+    /// Builds a notifier from the environment, or `None` when `SMTP_HOST` is unset:
+    ///
+    /// - `SMTP_HOST`, `SMTP_PORT` (default 587), `SMTP_SECURITY` (`starttls`, `tls` or `none`)
+    /// - `SMTP_USERNAME` / `SMTP_PASSWORD` - optional credentials
+    /// - `EMAIL_FROM`, `EMAIL_TO` (comma separated)
+    /// - `EMAIL_DIGEST` - `immediate` (default) or `daily`
+    /// - `EMAIL_DIGEST_HOUR` - hour of the daily digest, 0-23 server local time (default 8)
+    pub fn from_env(watches: Arc<WatchStore>) -> Result<Option<Self>, Error> {
+        let Ok(host) = env::var("SMTP_HOST") else {
+            return Ok(None);
+        };
+
+        let port = match env::var("SMTP_PORT") {
+            Ok(port) => port
+                .parse()
+                .map_err(|_| Error::Config(format!("Invalid SMTP_PORT '{}'", port)))?,
+            Err(_) => 587,
+        };
+        let security = match env::var("SMTP_SECURITY").unwrap_or_default().to_lowercase().as_str() {
+            "" | "starttls" => SmtpSecurity::StartTls,
+            "tls" => SmtpSecurity::Tls,
+            "none" => SmtpSecurity::None,
+            other => return Err(Error::Config(format!("Unknown SMTP_SECURITY '{}'", other))),
+        };
+        let credentials = match (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            _ => None,
+        };
+        let from = env::var("EMAIL_FROM")
+            .map_err(|_| Error::Config("EMAIL_FROM is required when SMTP_HOST is set".to_string()))?;
+        let to: Vec<String> = env::var("EMAIL_TO")
+            .map_err(|_| Error::Config("EMAIL_TO is required when SMTP_HOST is set".to_string()))?
+            .split(',')
+            .filter(|addr| !addr.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+        let mode = match env::var("EMAIL_DIGEST").unwrap_or_default().to_lowercase().as_str() {
+            "" | "immediate" => DigestMode::Immediate,
+            "daily" => DigestMode::Daily,
+            other => return Err(Error::Config(format!("Unknown EMAIL_DIGEST '{}'", other))),
+        };
+        let digest_at = match env::var("EMAIL_DIGEST_HOUR") {
+            Ok(hour) => hour
+                .trim()
+                .parse()
+                .ok()
+                .and_then(|hour| NaiveTime::from_hms_opt(hour, 0, 0))
+                .ok_or_else(|| {
+                    Error::Config(format!("Invalid EMAIL_DIGEST_HOUR '{}', expected 0-23", hour))
+                })?,
+            Err(_) => NaiveTime::from_hms_opt(8, 0, 0).expect("8:00 is a valid time"),
+        };
+
+        let notifier = Self::new(&host, port, security, credentials, &from, &to, watches)?;
+        Ok(Some(match mode {
+            DigestMode::Immediate => notifier,
+            DigestMode::Daily => notifier.with_daily_digest(digest_at),
+        }))
+    }
+
+    pub fn mode(&self) -> DigestMode {
+        self.mode
+    }
+
+    /// Sends every arrival recorded since the last digest, if any.
+    pub async fn flush(&self) -> Result<(), Error> {
+        let _sending = self.sending.lock().await;
+        let sent_up_to = self.watches.notifier_cursor(CURSOR).map_err(Error::Storage)?;
+        let events = self.watches.events_after(sent_up_to).map_err(Error::Storage)?;
+        let Some(last) = events.last() else {
+            return Ok(());
+        };
+
+        self.send_digest(&events).await?;
+        self.watches.set_notifier_cursor(CURSOR, last.id).map_err(Error::Storage)
+    }
+
+    /// Starts a task that sends the digest every day at the digest time.
+    pub fn spawn_daily_digest(&self) -> JoinHandle<()> {
+        let notifier = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let now = Local::now();
+                let wait = next_digest_at(&now, notifier.digest_at) - now;
+                tokio::time::sleep(wait.to_std().unwrap_or_default()).await;
+                if let Err(e) = notifier.flush().await {
+                    println!("Daily email digest failed: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn send_digest(&self, events: &[ArrivalEvent]) -> Result<(), Error> {
+        let subject = format!(
+            "Junkyard Tracker: {} new vehicle{}",
+            events.len(),
+            if events.len() == 1 { "" } else { "s" }
+        );
+
+        let mut builder = Message::builder().from(self.from.clone()).subject(subject);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let message = builder
+            .multipart(MultiPart::alternative_plain_html(
+                render_text(events),
+                render_html(events),
+            ))
+            .map_err(|e| Error::Message(e.to_string()))?;
+
+        self.transport.send(message).await.map_err(Error::Smtp)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        "email"
+    }
+
+    /// The arrivals are already in the watch store, so this only decides whether
+    /// to send them now or leave them for the daily digest.
+    async fn notify(&self, _events: &[ArrivalEvent]) -> Result<(), NotifyError> {
+        if self.mode == DigestMode::Immediate {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+/// The first time after `now` that the clock reads `at`, in `now`'s time zone.
+/// A day whose `at` is skipped by a clock change is passed over.
+fn next_digest_at<Tz: TimeZone>(now: &DateTime<Tz>, at: NaiveTime) -> DateTime<Tz> {
+    let mut day = now.date_naive();
+    loop {
+        if let Some(next) = day.and_time(at).and_local_timezone(now.timezone()).earliest() {
+            if next > *now {
+                return next;
+            }
+        }
+        day = day.succ_opt().expect("the digest falls within chrono's date range");
+    }
+}
+
+/// Arrivals grouped by the saved search that found them.
+fn group_by_search(events: &[ArrivalEvent]) -> BTreeMap<&str, Vec<&JunkyardItem>> {
+    let mut groups: BTreeMap<&str, Vec<&JunkyardItem>> = BTreeMap::new();
+    for event in events {
+        groups.entry(event.watch_name.as_str()).or_default().push(&event.vehicle);
+    }
+    groups
+}
+
+fn vehicle_title(vehicle: &JunkyardItem) -> String {
+    match vehicle.year {
+        Some(year) => format!("{} {} {}", year, vehicle.make, vehicle.model),
+        None => format!("{} {}", vehicle.make, vehicle.model),
+    }
+}
+
+fn set_date(vehicle: &JunkyardItem) -> String {
+    match vehicle.added_date {
+        Some(date) => date.format("%m/%d/%Y").to_string(),
+        None => "date unknown".to_string(),
+    }
+}
+
+/// e.g. "Pick-n-Pull Newark, 7400 Mowry Avenue, Newark, CA 94560 (14.8 mi)"
+fn store_line(vehicle: &JunkyardItem) -> String {
+    let Some(store) = &vehicle.store else {
+        return vehicle.location.clone().unwrap_or_else(|| "Unknown store".to_string());
+    };
+
+    let mut line = format!("Pick-n-Pull {}", store.name);
+    let address: Vec<&str> = [&store.address, &store.city]
+        .into_iter()
+        .filter_map(|part| part.as_deref())
+        .collect();
+    if !address.is_empty() {
+        line.push_str(&format!(", {}", address.join(", ")));
+    }
+    if let (Some(state), Some(zip)) = (&store.state, &store.zip) {
+        line.push_str(&format!(", {} {}", state, zip));
+    }
+    if let Some(distance) = store.distance_miles {
+        line.push_str(&format!(" ({} mi)", distance));
+    }
+    line
+}
+
+fn render_text(events: &[ArrivalEvent]) -> String {
+    let mut text = String::from("New vehicles matching your saved searches:\n");
+    for (search, vehicles) in group_by_search(events) {
+        text.push_str(&format!("\n{}\n", search));
+        for vehicle in vehicles {
+            text.push_str(&format!(
+                "- {} - Row {} - set {}\n  {}\n",
+                vehicle_title(vehicle),
+                vehicle.row.as_deref().unwrap_or("?"),
+                set_date(vehicle),
+                store_line(vehicle)
+            ));
+            if let Some(phone) = vehicle.store.as_ref().and_then(|s| s.phone.as_deref()) {
+                text.push_str(&format!("  Phone: {}\n", phone));
+            }
+        }
+    }
+    text
+}
+
+fn render_html(events: &[ArrivalEvent]) -> String {
+    let mut html = String::from("<html><body><h2>New vehicles matching your saved searches</h2>");
+    for (search, vehicles) in group_by_search(events) {
+        html.push_str(&format!("<h3>{}</h3><ul>", escape_html(search)));
+        for vehicle in vehicles {
+            html.push_str("<li>");
+            if let Some(photo) = vehicle.image_urls.first() {
+                html.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\" width=\"240\"><br>",
+                    escape_html(photo),
+                    escape_html(&vehicle_title(vehicle))
+                ));
+            }
+            html.push_str(&format!(
+                "<strong>{}</strong> &ndash; Row {} &ndash; set {}<br>",
+                escape_html(&vehicle_title(vehicle)),
+                escape_html(vehicle.row.as_deref().unwrap_or("?")),
+                set_date(vehicle)
+            ));
+            let store = escape_html(&store_line(vehicle));
+            match vehicle.store.as_ref().and_then(|s| s.url.as_deref()) {
+                Some(url) => html.push_str(&format!("<a href=\"{}\">{}</a>", escape_html(url), store)),
+                None => html.push_str(&store),
+            }
+            if let Some(phone) = vehicle.store.as_ref().and_then(|s| s.phone.as_deref()) {
+                html.push_str(&format!("<br>Phone: {}", escape_html(phone)));
+            }
+            html.push_str("</li>");
+        }
+        html.push_str("</ul>");
+    }
+    html.push_str("</body></html>");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::vehicle_key;
+    use crate::parser::parse_junkyard_page;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Minimal SMTP server that accepts one message and hands back its DATA.
+    async fn smtp_sink(listener: TcpListener) -> String {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        writer.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+
+        let mut data = String::new();
+        let mut in_data = false;
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if in_data {
+                if line == "." {
+                    writer.write_all(b"250 OK\r\n").await.unwrap();
+                    return data;
+                }
+                data.push_str(&line);
+                data.push('\n');
+                continue;
+            }
+
+            let command = line.to_uppercase();
+            let reply: &[u8] = if command.starts_with("EHLO") {
+                b"250-sink\r\n250 8BITMIME\r\n"
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                b"354 End data with <CR><LF>.<CR><LF>\r\n"
+            } else {
+                b"250 OK\r\n"
+            };
+            writer.write_all(reply).await.unwrap();
+        }
+        data
+    }
+
+    fn notifier(port: u16, watches: &Arc<WatchStore>) -> EmailNotifier {
+        EmailNotifier::new(
+            "127.0.0.1",
+            port,
+            SmtpSecurity::None,
+            None,
+            "tracker@example.com",
+            &["mechanic@example.com".to_string()],
+            watches.clone(),
+        )
+        .unwrap()
+    }
+
+    /// Records the sample page's vehicle as a new arrival for a "wagon donors" watch.
+    fn record_arrival(watches: &WatchStore) -> Vec<ArrivalEvent> {
+        let search = serde_json::from_value(serde_json::json!({
+            "make": "Subaru", "model": "Impreza Wagon", "zip_code": "94560"
+        }))
+        .unwrap();
+        let watch = watches.create("wagon donors", "sam", &search).unwrap();
+        let vehicle = parse_junkyard_page(include_str!("../firecrawl_sample_output.md"), "").remove(0);
+        let run_at = chrono::Utc::now();
+        watches.record_run(&watch, &[], run_at).unwrap();
+        let watch = watches.get(watch.id).unwrap().unwrap();
+        watches
+            .record_run(&watch, &[(vehicle_key(&vehicle), vehicle)], run_at)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_immediate_digest_to_smtp_sink() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(smtp_sink(listener));
+
+        let watches = Arc::new(WatchStore::open_in_memory().unwrap());
+        let notifier = notifier(port, &watches);
+        let events = record_arrival(&watches);
+        notifier.notify(&events).await.unwrap();
+
+        let data = sink.await.unwrap();
+        assert!(data.contains("Subject: Junkyard Tracker: 1 new vehicle"));
+        assert!(data.contains("Row 132"));
+        assert!(data.contains("7400 Mowry Avenue"));
+        assert!(data.contains("text/html"));
+
+        // Already sent, so there's nothing left to email
+        notifier.flush().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn daily_digest_survives_a_restart() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let sink = tokio::spawn(smtp_sink(listener));
+        let watches = Arc::new(WatchStore::open_in_memory().unwrap());
+        let eight = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+
+        let before_restart = notifier(port, &watches).with_daily_digest(eight);
+        let events = record_arrival(&watches);
+        before_restart.notify(&events).await.unwrap();
+        drop(before_restart);
+        assert!(!sink.is_finished());
+
+        notifier(port, &watches).with_daily_digest(eight).flush().await.unwrap();
+        let data = sink.await.unwrap();
+        assert!(data.contains("Subject: Junkyard Tracker: 1 new vehicle"));
+        assert!(data.contains("wagon donors"));
+    }
+
+    #[test]
+    fn schedules_the_digest_at_a_fixed_hour() {
+        let eight = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+        let at = |time: &str| {
+            chrono::Utc.from_utc_datetime(&format!("2025-04-09T{}", time).parse().unwrap())
+        };
+
+        assert_eq!(next_digest_at(&at("07:30:00"), eight), at("08:00:00"));
+        let tomorrow = at("08:00:00") + chrono::Duration::days(1);
+        assert_eq!(next_digest_at(&at("08:00:00"), eight), tomorrow);
+        assert_eq!(next_digest_at(&at("23:59:00"), eight), tomorrow);
+    }
+}
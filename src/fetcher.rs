@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::firecrawl_client::{parse_retry_after, CrawlData, CrawlResponse, Error, FirecrawlClient, ScrapeFormat};
+
+/// Something that can fetch a Pick-n-Pull results page for the parser.
+#[async_trait]
+pub trait PageFetcher: Send + Sync {
+    async fn fetch_page(&self, url: &str, formats: &[ScrapeFormat]) -> Result<CrawlResponse, Error>;
+}
+
+/// The fetcher named by `backend` ("firecrawl" or "direct"), as set in `FETCH_BACKEND`.
+/// Firecrawl is configured from the `FIRECRAWL_*` variables.
+pub fn from_env(backend: &str) -> Result<Arc<dyn PageFetcher>, Error> {
+    match backend {
+        "direct" => Ok(Arc::new(DirectFetcher::new()?)),
+        "firecrawl" => Ok(Arc::new(FirecrawlClient::from_env()?)),
+        other => Err(Error::Config(format!(
+            "Unknown FETCH_BACKEND '{}', expected 'firecrawl' or 'direct'",
+            other
+        ))),
+    }
+}
+
+#[async_trait]
+impl PageFetcher for FirecrawlClient {
+    async fn fetch_page(&self, url: &str, formats: &[ScrapeFormat]) -> Result<CrawlResponse, Error> {
+        self.crawl_webpage_with_formats(url, formats).await
+    }
+}
+
+/// Fetches pages directly over HTTP, without going through Firecrawl.
+///
+/// Only the raw HTML is returned, so pair it with the `html` or `auto` parser.
+pub struct DirectFetcher {
+    client: Client,
+}
+
+/// How long a direct page fetch may take before it fails with [`Error::Timeout`].
+pub const DIRECT_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+impl DirectFetcher {
+    pub fn new() -> Result<Self, Error> {
+        Self::with_timeout(DIRECT_FETCH_TIMEOUT)
+    }
+
+    pub fn with_timeout(timeout: Duration) -> Result<Self, Error> {
+        let client = Client::builder()
+            .user_agent("Mozilla/5.0 (compatible; junkyardTracker/0.1)")
+            .timeout(timeout)
+            .build()
+            .map_err(|e| Error::Config(format!("Could not build the HTTP client: {}", e)))?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl PageFetcher for DirectFetcher {
+    async fn fetch_page(&self, url: &str, _formats: &[ScrapeFormat]) -> Result<CrawlResponse, Error> {
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(Error::from_reqwest)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let retry_after = parse_retry_after(response.headers());
+            return Err(Error::from_status(status, retry_after, format!("Page fetch failed for {}", url)));
+        }
+
+        let final_url = response.url().to_string();
+        let html = response.text().await.map_err(Error::from_reqwest)?;
+
+        Ok(CrawlResponse {
+            success: true,
+            data: Some(CrawlData {
+                markdown: None,
+                html: Some(html),
+                url: Some(final_url),
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use crate::test_support::spawn_server;
+
+    #[tokio::test]
+    async fn direct_fetcher_returns_page_html() {
+        let app = Router::new().route("/page", get(|| async { "<table></table>" }));
+        let addr = spawn_server(app).await;
+
+        let response = DirectFetcher::new()
+            .unwrap()
+            .fetch_page(&format!("http://{}/page", addr), &[ScrapeFormat::Html])
+            .await
+            .unwrap();
+
+        let data = response.data.unwrap();
+        assert_eq!(data.html.as_deref(), Some("<table></table>"));
+        assert!(data.markdown.is_none());
+
+        assert!(matches!(from_env("carrier-pigeon"), Err(Error::Config(_))));
+    }
+
+    #[tokio::test]
+    async fn direct_fetcher_gives_up_on_slow_pages() {
+        let app = Router::new().route(
+            "/slow",
+            get(|| async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                "<table></table>"
+            }),
+        );
+        let addr = spawn_server(app).await;
+
+        let result = DirectFetcher::with_timeout(Duration::from_millis(100))
+            .unwrap()
+            .fetch_page(&format!("http://{}/slow", addr), &[ScrapeFormat::Html])
+            .await;
+        assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+    }
+}
@@ -1,6 +1,17 @@
-use reqwest::Client;
+use rand::Rng;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::env;
 use std::error::Error as StdError;
+use std::str::FromStr;
+use std::time::Duration;
+
+const DEFAULT_BASE_URL: &str = "https://api.firecrawl.dev";
+const DEFAULT_MAX_AGE_MS: u64 = 14_400_000; // 4 hours
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_RETRY_BASE_MS: u64 = 500;
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CrawlResponse {
@@ -15,10 +26,69 @@ pub struct CrawlData {
     pub url: Option<String>,
 }
 
+/// Output formats Firecrawl can return for a scraped page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrapeFormat {
+    Markdown,
+    Html,
+}
+
+impl FromStr for ScrapeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "markdown" => Ok(ScrapeFormat::Markdown),
+            "html" => Ok(ScrapeFormat::Html),
+            other => Err(format!("Unknown scrape format: {}", other)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     RequestFailed(reqwest::Error),
     ApiError(String),
+    Config(String),
+    RateLimited { retry_after: Option<Duration> },
+    Timeout,
+    Unauthorized(String),
+    Upstream { status: u16, message: String },
+}
+
+impl Error {
+    /// Classifies a non-success HTTP response.
+    pub(crate) fn from_status(status: StatusCode, retry_after: Option<Duration>, message: String) -> Self {
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited { retry_after },
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Error::Unauthorized(message),
+            StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => Error::Timeout,
+            s if s.is_server_error() => Error::Upstream { status: s.as_u16(), message },
+            s => Error::ApiError(format!(
+                "API returned error status: {}, message: {}",
+                s,
+                message
+            )),
+        }
+    }
+
+    pub(crate) fn from_reqwest(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Error::Timeout
+        } else {
+            Error::RequestFailed(e)
+        }
+    }
+
+    /// Whether the same request might succeed if sent again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RateLimited { .. } | Error::Timeout | Error::Upstream { .. } => true,
+            Error::RequestFailed(e) => e.is_connect(),
+            Error::ApiError(_) | Error::Config(_) | Error::Unauthorized(_) => false,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -26,57 +96,443 @@ impl std::fmt::Display for Error {
         match self {
             Error::RequestFailed(e) => write!(f, "Request failed: {}", e),
             Error::ApiError(msg) => write!(f, "API error: {}", msg),
+            Error::Config(msg) => write!(f, "Configuration error: {}", msg),
+            Error::RateLimited { retry_after: Some(delay) } => {
+                write!(f, "Rate limited, retry after {}s", delay.as_secs())
+            }
+            Error::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            Error::Timeout => write!(f, "Request timed out"),
+            Error::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
+            Error::Upstream { status, message } => {
+                write!(f, "Upstream error status: {}, message: {}", status, message)
+            }
         }
     }
 }
 
 impl StdError for Error {}
 
+/// Reads a `Retry-After` header given either as seconds or as an HTTP date.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
 pub struct FirecrawlClient {
-    api_key: String,
+    api_key: Option<String>,
     base_url: String,
     client: Client,
+    max_age_ms: Option<u64>,
+    formats: Vec<ScrapeFormat>,
+    wait_for_ms: Option<u64>,
+    only_main_content: bool,
+    parse_pdf: bool,
+    max_retries: u32,
+    retry_base: Duration,
 }
 
-impl FirecrawlClient {
-    pub fn new(api_key: String) -> Self {
+/// Builder for [`FirecrawlClient`], for self-hosted instances or non-default scrape options.
+pub struct FirecrawlClientBuilder {
+    api_key: Option<String>,
+    base_url: String,
+    timeout: Option<Duration>,
+    max_age_ms: Option<u64>,
+    formats: Vec<ScrapeFormat>,
+    wait_for_ms: Option<u64>,
+    only_main_content: bool,
+    parse_pdf: bool,
+    headers: Vec<(String, String)>,
+    max_retries: u32,
+    retry_base: Duration,
+}
+
+impl Default for FirecrawlClientBuilder {
+    fn default() -> Self {
         Self {
-            api_key,
-            base_url: "https://api.firecrawl.dev".to_string(),
-            client: Client::new(),
+            api_key: None,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            timeout: None,
+            max_age_ms: Some(DEFAULT_MAX_AGE_MS),
+            formats: vec![ScrapeFormat::Markdown],
+            wait_for_ms: None,
+            only_main_content: true,
+            parse_pdf: true,
+            headers: Vec::new(),
+            max_retries: DEFAULT_MAX_RETRIES,
+            retry_base: Duration::from_millis(DEFAULT_RETRY_BASE_MS),
+        }
+    }
+}
+
+impl FirecrawlClientBuilder {
+    /// Reads the client settings from the environment:
+    ///
+    /// - `FIRECRAWL_API_KEY` - API key, optional for self-hosted instances
+    /// - `FIRECRAWL_BASE_URL` - defaults to `https://api.firecrawl.dev`
+    /// - `FIRECRAWL_TIMEOUT_SECS` - request timeout
+    /// - `FIRECRAWL_MAX_AGE_MS` - cache age Firecrawl may serve, `0` to always scrape fresh
+    /// - `FIRECRAWL_FORMATS` - comma separated, e.g. `markdown,html`
+    /// - `FIRECRAWL_WAIT_FOR_MS` - delay before Firecrawl captures the page
+    /// - `FIRECRAWL_ONLY_MAIN_CONTENT` / `FIRECRAWL_PARSE_PDF` - `true` or `false`
+    /// - `FIRECRAWL_HEADERS` - extra request headers, `Name: value` pairs separated by `;`
+    /// - `FIRECRAWL_MAX_RETRIES` / `FIRECRAWL_RETRY_BASE_MS` - retry count and first backoff delay
+    ///
+    /// `FIRECRAWL_FORMATS` only sets the formats of [`FirecrawlClient::crawl_webpage`].
+    /// The API server asks for the formats its `parser` setting needs on each
+    /// request, so the variable has no effect there.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    /// Like [`from_env`](Self::from_env), reading each variable through `var`.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let mut builder = Self::default();
+
+        if let Some(api_key) = var("FIRECRAWL_API_KEY") {
+            builder = builder.api_key(api_key);
+        }
+        if let Some(base_url) = var("FIRECRAWL_BASE_URL") {
+            builder = builder.base_url(base_url);
+        }
+        if let Some(secs) = parse_var::<u64>(&var, "FIRECRAWL_TIMEOUT_SECS")? {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+        if let Some(max_age) = parse_var::<u64>(&var, "FIRECRAWL_MAX_AGE_MS")? {
+            builder = builder.max_age_ms(max_age);
+        }
+        if let Some(formats) = var("FIRECRAWL_FORMATS") {
+            let formats = formats
+                .split(',')
+                .filter(|f| !f.trim().is_empty())
+                .map(ScrapeFormat::from_str)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(Error::Config)?;
+            builder = builder.formats(formats);
+        }
+        if let Some(wait_for) = parse_var::<u64>(&var, "FIRECRAWL_WAIT_FOR_MS")? {
+            builder = builder.wait_for_ms(wait_for);
+        }
+        if let Some(only_main_content) = parse_var::<bool>(&var, "FIRECRAWL_ONLY_MAIN_CONTENT")? {
+            builder = builder.only_main_content(only_main_content);
+        }
+        if let Some(parse_pdf) = parse_var::<bool>(&var, "FIRECRAWL_PARSE_PDF")? {
+            builder = builder.parse_pdf(parse_pdf);
+        }
+        if let Some(headers) = var("FIRECRAWL_HEADERS") {
+            for pair in headers.split(';').filter(|p| !p.trim().is_empty()) {
+                let (name, value) = pair.split_once(':').ok_or_else(|| {
+                    Error::Config(format!("FIRECRAWL_HEADERS entry '{}' is not 'Name: value'", pair))
+                })?;
+                builder = builder.header(name.trim(), value.trim());
+            }
+        }
+
+        if let Some(max_retries) = parse_var::<u32>(&var, "FIRECRAWL_MAX_RETRIES")? {
+            builder = builder.max_retries(max_retries);
+        }
+        if let Some(retry_base) = parse_var::<u64>(&var, "FIRECRAWL_RETRY_BASE_MS")? {
+            builder = builder.retry_base(Duration::from_millis(retry_base));
+        }
+
+        Ok(builder)
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// `0` asks Firecrawl to always scrape a fresh copy.
+    pub fn max_age_ms(mut self, max_age_ms: u64) -> Self {
+        self.max_age_ms = Some(max_age_ms);
+        self
+    }
+
+    pub fn formats(mut self, formats: Vec<ScrapeFormat>) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    pub fn wait_for_ms(mut self, wait_for_ms: u64) -> Self {
+        self.wait_for_ms = Some(wait_for_ms);
+        self
+    }
+
+    pub fn only_main_content(mut self, only_main_content: bool) -> Self {
+        self.only_main_content = only_main_content;
+        self
+    }
+
+    pub fn parse_pdf(mut self, parse_pdf: bool) -> Self {
+        self.parse_pdf = parse_pdf;
+        self
+    }
+
+    /// Adds a header sent with every request to the Firecrawl API.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Retries after rate limits, timeouts and 5xx responses; `0` disables retrying.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// First backoff delay, doubled on each further attempt.
+    pub fn retry_base(mut self, retry_base: Duration) -> Self {
+        self.retry_base = retry_base;
+        self
+    }
+
+    pub fn build(self) -> Result<FirecrawlClient, Error> {
+        // The hosted API always needs a key; self-hosted instances may not
+        if self.api_key.is_none() && self.base_url == DEFAULT_BASE_URL {
+            return Err(Error::Config(
+                "FIRECRAWL_API_KEY is required for the hosted Firecrawl API".to_string(),
+            ));
+        }
+        if self.formats.is_empty() {
+            return Err(Error::Config("At least one scrape format is required".to_string()));
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::Config(format!("Invalid header name '{}': {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| Error::Config(format!("Invalid header value for '{}': {}", name, e)))?;
+            headers.insert(name, value);
+        }
+
+        let mut client = Client::builder().default_headers(headers);
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
         }
+        let client = client.build().map_err(Error::RequestFailed)?;
+
+        Ok(FirecrawlClient {
+            api_key: self.api_key,
+            base_url: self.base_url,
+            client,
+            max_age_ms: self.max_age_ms,
+            formats: self.formats,
+            wait_for_ms: self.wait_for_ms,
+            only_main_content: self.only_main_content,
+            parse_pdf: self.parse_pdf,
+            max_retries: self.max_retries,
+            retry_base: self.retry_base,
+        })
+    }
+}
+
+fn parse_var<T: FromStr>(var: impl Fn(&str) -> Option<String>, name: &str) -> Result<Option<T>, Error> {
+    match var(name) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| Error::Config(format!("Invalid value for {}: '{}'", name, value))),
+        None => Ok(None),
+    }
+}
+
+impl FirecrawlClient {
+    pub fn new(api_key: String) -> Self {
+        Self::builder()
+            .api_key(api_key)
+            .build()
+            .expect("default Firecrawl client configuration is valid")
+    }
+
+    pub fn builder() -> FirecrawlClientBuilder {
+        FirecrawlClientBuilder::default()
+    }
+
+    /// Builds a client from the `FIRECRAWL_*` environment variables.
+    pub fn from_env() -> Result<Self, Error> {
+        FirecrawlClientBuilder::from_env()?.build()
     }
 
     pub async fn crawl_webpage(&self, url: &str) -> Result<CrawlResponse, Error> {
-        let response = self
+        self.crawl_webpage_with_formats(url, &self.formats).await
+    }
+
+    pub async fn crawl_webpage_with_formats(&self, url: &str, formats: &[ScrapeFormat])
+        -> Result<CrawlResponse, Error> {
+        let mut attempt = 0;
+        loop {
+            match self.scrape_once(url, formats).await {
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    let delay = match &e {
+                        Error::RateLimited { retry_after: Some(delay) } => (*delay).min(MAX_RETRY_DELAY),
+                        _ => self.backoff_delay(attempt),
+                    };
+                    println!("Firecrawl request failed ({}), retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Exponential backoff with jitter: a random delay between half and all of
+    /// `retry_base * 2^attempt`, capped at 30 seconds.
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let ceiling = self.retry_base
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_RETRY_DELAY);
+        let ceiling_ms = ceiling.as_millis() as u64;
+        let jittered = rand::thread_rng().gen_range(ceiling_ms / 2..=ceiling_ms);
+        Duration::from_millis(jittered)
+    }
+
+    async fn scrape_once(&self, url: &str, formats: &[ScrapeFormat]) -> Result<CrawlResponse, Error> {
+        let mut body = serde_json::json!({
+            "url": url,
+            "formats": formats,
+            "onlyMainContent": self.only_main_content,
+            "parsePDF": self.parse_pdf,
+        });
+        if let Some(max_age) = self.max_age_ms {
+            body["maxAge"] = max_age.into();
+        }
+        if let Some(wait_for) = self.wait_for_ms {
+            body["waitFor"] = wait_for.into();
+        }
+
+        let mut request = self
             .client
-            .post(&format!("{}/v1/scrape", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({
-                "url": url,
-                "formats": ["markdown"],
-                "onlyMainContent": true,
-                "parsePDF": true,
-                "maxAge": 14400000
-            }))
+            .post(format!("{}/v1/scrape", self.base_url))
+            .header("Content-Type", "application/json");
+        if let Some(api_key) = &self.api_key {
+            request = request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = request
+            .json(&body)
             .send()
             .await
-            .map_err(Error::RequestFailed)?;
+            .map_err(Error::from_reqwest)?;
 
         if !response.status().is_success() {
             let status = response.status();
+            let retry_after = parse_retry_after(response.headers());
             let error_text = response.text().await.unwrap_or_default();
-            return Err(Error::ApiError(format!(
-                "API returned error status: {}, message: {}",
-                status,
-                error_text
-            )));
+            return Err(Error::from_status(status, retry_after, error_text));
         }
 
         let crawl_response = response.json::<CrawlResponse>().await
-            .map_err(Error::RequestFailed)?;
+            .map_err(Error::from_reqwest)?;
 
         Ok(crawl_response)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode as AxumStatus, routing::post, Json, Router};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use crate::test_support::spawn_server;
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn builds_from_environment_variables() {
+        let vars = |pairs: &[(&str, &str)]| {
+            let vars: std::collections::HashMap<String, String> =
+                pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            move |name: &str| vars.get(name).cloned()
+        };
+
+        let builder = FirecrawlClientBuilder::from_vars(vars(&[
+            ("FIRECRAWL_BASE_URL", "http://localhost:3002/"),
+            ("FIRECRAWL_FORMATS", "markdown, html"),
+            ("FIRECRAWL_HEADERS", "X-Team: junkyard; X-Trace:on;"),
+            ("FIRECRAWL_MAX_RETRIES", " 5 "),
+        ]))
+        .unwrap();
+        assert_eq!(builder.base_url, "http://localhost:3002");
+        assert_eq!(builder.formats, [ScrapeFormat::Markdown, ScrapeFormat::Html]);
+        assert_eq!(
+            builder.headers,
+            [("X-Team".to_string(), "junkyard".to_string()), ("X-Trace".to_string(), "on".to_string())]
+        );
+        assert_eq!(builder.max_retries, 5);
+        // Self-hosted instances don't need a key
+        assert!(builder.build().is_ok());
+
+        let missing_key = FirecrawlClientBuilder::from_vars(vars(&[])).unwrap().build();
+        assert!(matches!(missing_key, Err(Error::Config(_))));
+        for bad in [
+            ("FIRECRAWL_HEADERS", "X-Team junkyard"),
+            ("FIRECRAWL_TIMEOUT_SECS", "soon"),
+            ("FIRECRAWL_FORMATS", "pdf"),
+        ] {
+            assert!(matches!(FirecrawlClientBuilder::from_vars(vars(&[bad])), Err(Error::Config(_))), "{:?}", bad);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limited_requests() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let app = Router::new().route("/v1/scrape", post(move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err((AxumStatus::TOO_MANY_REQUESTS, [("retry-after", "0")]))
+                } else {
+                    Ok(Json(serde_json::json!({
+                        "success": true,
+                        "data": { "markdown": "## Matching Vehicles" }
+                    })))
+                }
+            }
+        }));
+        let addr = spawn_server(app).await;
+
+        let client = FirecrawlClient::builder()
+            .base_url(format!("http://{}", addr))
+            .retry_base(Duration::from_millis(1))
+            .build()
+            .unwrap();
+
+        let response = client.crawl_webpage("https://example.com").await.unwrap();
+        assert!(response.success);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let client = FirecrawlClient::builder()
+            .base_url(format!("http://{}", addr))
+            .max_retries(0)
+            .build()
+            .unwrap();
+        calls.store(0, Ordering::SeqCst);
+        let err = client.crawl_webpage("https://example.com").await.unwrap_err();
+        assert!(matches!(err, Error::RateLimited { .. }));
+    }
+}
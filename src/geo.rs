@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::data_file::DataFile;
+use crate::models::{JunkyardItem, SortOrder};
+
+const EARTH_RADIUS_MILES: f64 = 3958.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Offline lookup of zip/postal code centroids.
+#[derive(Debug, Default)]
+pub struct ZipCentroids {
+    exact: HashMap<String, Coordinates>,
+    // Listed sectional centers and FSAs, else the average of the listed codes sharing
+    // a prefix, for codes that aren't listed
+    prefixes: HashMap<String, Coordinates>,
+    // Canadian postal districts (a code's first letter)
+    districts: HashMap<String, Coordinates>,
+}
+
+/// A CSV of `postal_code,latitude,longitude` lines; `#` comments and a header are
+/// skipped. A three-character code is a prefix's centroid and a one-letter code a
+/// Canadian district's. The bundled copy is `data/zip_centroids.csv`.
+impl DataFile for ZipCentroids {
+    const NAME: &'static str = "zip centroids";
+    const BUNDLED: &'static str = include_str!("../data/zip_centroids.csv");
+
+    fn parse(csv: &str) -> Result<Self, String> {
+        let mut exact = HashMap::new();
+        let mut prefixes = HashMap::new();
+        let mut districts = HashMap::new();
+        for (index, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("postal_code") {
+                continue;
+            }
+            let parse_error = || format!("line {}: {}", index + 1, line);
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [code, latitude, longitude] = fields[..] else {
+                return Err(parse_error());
+            };
+            let coordinates = Coordinates {
+                latitude: latitude.parse().map_err(|_| parse_error())?,
+                longitude: longitude.parse().map_err(|_| parse_error())?,
+            };
+            let code = normalize_code(code);
+            let table = match code.len() {
+                1 => &mut districts,
+                3 => &mut prefixes,
+                _ => &mut exact,
+            };
+            table.insert(code, coordinates);
+        }
+
+        let mut sums: HashMap<String, (f64, f64, usize)> = HashMap::new();
+        for (code, coordinates) in &exact {
+            let sum = sums.entry(prefix(code).to_string()).or_default();
+            sum.0 += coordinates.latitude;
+            sum.1 += coordinates.longitude;
+            sum.2 += 1;
+        }
+        for (prefix, (latitude, longitude, count)) in sums {
+            let count = count as f64;
+            prefixes
+                .entry(prefix)
+                .or_insert(Coordinates { latitude: latitude / count, longitude: longitude / count });
+        }
+
+        Ok(Self { exact, prefixes, districts })
+    }
+}
+
+impl ZipCentroids {
+    /// Where a zip ("94560", "94560-1234") or postal code ("T2B 1N2") is, if known.
+    pub fn locate(&self, code: &str) -> Option<Coordinates> {
+        let code = normalize_code(code);
+        // ZIP+4 codes are located by their five-digit zip
+        let code = match code.split_once('-') {
+            Some((zip, _)) => zip.to_string(),
+            None => code,
+        };
+        self.exact
+            .get(&code)
+            .or_else(|| self.prefixes.get(prefix(&code)))
+            .or_else(|| district(&code).and_then(|district| self.districts.get(district)))
+            .copied()
+    }
+
+    /// Great-circle distance between two codes, when both can be located.
+    pub fn distance_between(&self, from: &str, to: &str) -> Option<f64> {
+        Some(distance_miles(self.locate(from)?, self.locate(to)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.exact.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty()
+    }
+}
+
+fn normalize_code(code: &str) -> String {
+    code.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase()
+}
+
+// First three characters: the US sectional center or the Canadian FSA
+fn prefix(code: &str) -> &str {
+    code.get(..3).unwrap_or(code)
+}
+
+// First letter of a Canadian postal code, which starts with one; zips don't
+fn district(code: &str) -> Option<&str> {
+    code.get(..1).filter(|letter| letter.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// Haversine distance in miles.
+pub fn distance_miles(a: Coordinates, b: Coordinates) -> f64 {
+    let (lat_a, lat_b) = (a.latitude.to_radians(), b.latitude.to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lon = (b.longitude - a.longitude).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_MILES * h.sqrt().asin()
+}
+
+/// Sets each vehicle's store `geo_distance_miles` from `origin`, so results merged
+/// from several searches can be compared.
+///
+/// Returns false, leaving the vehicles as they are, when `origin` can't be located.
+pub fn annotate_distances(items: &mut [JunkyardItem], origin: &str, centroids: &ZipCentroids) -> bool {
+    let Some(origin) = centroids.locate(origin) else {
+        return false;
+    };
+    for store in items.iter_mut().filter_map(|item| item.store.as_mut()) {
+        store.geo_distance_miles = store
+            .zip
+            .as_deref()
+            .and_then(|zip| centroids.locate(zip))
+            .map(|location| (distance_miles(origin, location) * 10.0).round() / 10.0);
+    }
+    true
+}
+
+/// The distance used for sorting and filtering: computed when known, else the site's.
+pub fn item_distance(item: &JunkyardItem) -> Option<f64> {
+    let store = item.store.as_ref()?;
+    store.geo_distance_miles.or(store.distance_miles)
+}
+
+/// Drops vehicles further than `max_miles` (those with no known distance are kept)
+/// and orders the rest.
+pub fn filter_and_sort(items: &mut Vec<JunkyardItem>, max_miles: Option<f64>, order: Option<SortOrder>) {
+    if let Some(max_miles) = max_miles {
+        items.retain(|item| item_distance(item).is_none_or(|miles| miles <= max_miles));
+    }
+    match order {
+        // Unknown distances go last
+        Some(SortOrder::Distance) => items.sort_by(|a, b| {
+            let key = |item| item_distance(item).unwrap_or(f64::INFINITY);
+            key(a).total_cmp(&key(b))
+        }),
+        Some(SortOrder::Newest) => items.sort_by_key(|item| std::cmp::Reverse(item.added_date)),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_junkyard_page;
+
+    #[test]
+    fn locates_codes_and_sorts_by_distance() {
+        let centroids = ZipCentroids::bundled();
+        assert!(centroids.locate("t2b1n2").is_some());
+        assert!(centroids.locate("94560-1234").is_some());
+        // Not listed, but shares its prefix with Bay Area yards
+        assert!(centroids.locate("94568").is_some());
+        assert!(centroids.locate("00000").is_none());
+        // Far from any yard: located by sectional center, or by district in Canada
+        let manhattan = centroids.locate("10001").unwrap();
+        assert!((manhattan.latitude - 40.75).abs() < 0.5 && (manhattan.longitude + 73.99).abs() < 0.5);
+        let halifax = centroids.locate("B3H 4R2").unwrap();
+        assert!((halifax.latitude - 44.65).abs() < 0.5, "{:?}", halifax);
+        assert!(centroids.locate("Z1A 1A1").is_none());
+        // Yard zips keep their own centroid over their sectional center's
+        assert_eq!(centroids.locate("94560").unwrap().latitude, 37.53);
+
+        // Newark to Oakland is roughly 17 miles as the crow flies
+        let miles = centroids.distance_between("94560", "94621").unwrap();
+        assert!((14.0..20.0).contains(&miles), "{}", miles);
+
+        let mut items = parse_junkyard_page(include_str!("../firecrawl_sample_output.md"), "");
+        annotate_distances(&mut items, "95014", &centroids);
+        let newark = items[0].store.as_ref().unwrap();
+        assert!(newark.geo_distance_miles.unwrap() > 10.0);
+
+        filter_and_sort(&mut items, Some(5.0), Some(SortOrder::Distance));
+        assert!(items.is_empty());
+    }
+}
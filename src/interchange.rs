@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use crate::data_file::{self, DataFile};
+use crate::models::{ParserMode, SearchRadius, SearchRequest};
+use crate::normalize;
+
+/// Which vehicles a part can be pulled from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Interchange {
+    pub parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Part {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub fits: Vec<Fitment>,
+}
+
+/// A make/model and the model years a part fits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fitment {
+    pub make: String,
+    pub model: String,
+    pub year_min: u32,
+    pub year_max: u32,
+}
+
+/// The bundled copy in `data/interchange.json` covers a handful of common pulls;
+/// INTERCHANGE_PATH points the server at a fuller list in the same layout.
+impl DataFile for Interchange {
+    const NAME: &'static str = "interchange data";
+    const BUNDLED: &'static str = include_str!("../data/interchange.json");
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        data_file::parse_json(contents)
+    }
+}
+
+impl Interchange {
+    /// Parts matching `query`: an exact part id, or every word of the query found
+    /// in the part's name or one of its aliases ("ej25 engine", "impreza headlight").
+    ///
+    /// Years in the query ("2004", "02-07") narrow it to parts fitting those years.
+    pub fn find(&self, query: &str) -> Vec<&Part> {
+        let years = query_years(query);
+        let words: Vec<String> = query
+            .split_whitespace()
+            .filter(|word| parse_years(word).is_none())
+            .map(normalize::fold)
+            .filter(|word| !word.is_empty())
+            .collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        self.parts
+            .iter()
+            .filter(|part| {
+                part.id.eq_ignore_ascii_case(query.trim())
+                    || (part.names().any(|name| {
+                        let name = normalize::fold(name);
+                        words.iter().all(|word| name.contains(word.as_str()))
+                    }) && years.is_none_or(|years| part.fits_years(years)))
+            })
+            .collect()
+    }
+
+    /// Part names close to a query that matched nothing.
+    pub fn suggest(&self, query: &str) -> Vec<String> {
+        normalize::suggest(query, self.parts.iter().flat_map(Part::names))
+    }
+}
+
+impl Part {
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    fn fits_years(&self, (from, to): (u32, u32)) -> bool {
+        self.fits.iter().any(|fit| fit.year_min <= to && fit.year_max >= from)
+    }
+}
+
+/// The model years a part query names ("impreza headlight 2005"), if any.
+pub fn query_years(query: &str) -> Option<(u32, u32)> {
+    query.split_whitespace().rev().find_map(parse_years)
+}
+
+/// Reads "2004", "04", "2002-2007" or "02-07" as a model year range.
+fn parse_years(word: &str) -> Option<(u32, u32)> {
+    let year = |s: &str| -> Option<u32> {
+        if !s.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        match (s.len(), s.parse::<u32>().ok()?) {
+            (4, year) => Some(year),
+            // Two-digit years: 50-99 are 1900s, the rest 2000s
+            (2, year) if year >= 50 => Some(1900 + year),
+            (2, year) => Some(2000 + year),
+            _ => None,
+        }
+    };
+    match word.split_once('-') {
+        Some((from, to)) => Some((year(from)?, year(to)?)),
+        None => year(word).map(|year| (year, year)),
+    }
+}
+
+impl Fitment {
+    /// The yard search that finds donor vehicles for this fitment, narrowed to the
+    /// query's `years` when it named any. `None` when the fitment has none of them.
+    pub fn search_request(
+        &self,
+        years: Option<(u32, u32)>,
+        zip_code: &str,
+        distance: Option<SearchRadius>,
+        parser: Option<ParserMode>,
+    ) -> Option<SearchRequest> {
+        let (from, to) = years.unwrap_or((self.year_min, self.year_max));
+        let (year_min, year_max) = (from.max(self.year_min), to.min(self.year_max));
+        if year_min > year_max {
+            return None;
+        }
+        Some(SearchRequest {
+            make: self.make.clone(),
+            models: vec![self.model.clone()],
+            year_min: Some(year_min),
+            year_max: Some(year_max),
+            zip_code: zip_code.to_string(),
+            distance,
+            parser,
+            store_id: None,
+            sort: None,
+            max_miles: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_parts_by_name_alias_and_id() {
+        let interchange = Interchange::bundled();
+
+        let ids = |query| interchange.find(query).iter().map(|p| p.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids("2.5L EJ25 engine"), ["ej25-engine"]);
+        assert_eq!(ids("ej253"), ["ej25-engine"]);
+        assert_eq!(ids("prius-gen2-hybrid-battery"), ["prius-gen2-hybrid-battery"]);
+        assert_eq!(ids("impreza headlight").len(), 3);
+        assert_eq!(ids("Impreza 02-07 headlight").len(), 3);
+        assert_eq!(ids("impreza headlight 2005"), ["impreza-headlight-04-05"]);
+        assert!(ids("flux capacitor").is_empty());
+
+        let fit = &interchange.find("k24")[0].fits[1];
+        let search = fit.search_request(None, "94560", Some(SearchRadius::Miles25), None).unwrap();
+        assert_eq!((search.make.as_str(), search.year_min, search.year_max), ("Honda", Some(2002), Some(2011)));
+        assert_eq!(search.models, ["CR-V"]);
+
+        // Years in the query narrow each fitment's search to the years both cover
+        let years = query_years("k24 engine 2009-2015");
+        let search = fit.search_request(years, "94560", None, None).unwrap();
+        assert_eq!((search.year_min, search.year_max), (Some(2009), Some(2011)));
+        assert!(fit.search_request(query_years("k24 1995"), "94560", None, None).is_none());
+    }
+}
@@ -0,0 +1,444 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::error::Error as StdError;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::models::{JunkyardItem, Sighting, Store, StoreInventoryChanges, VehicleHistory};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS vehicles (
+    vehicle_key TEXT PRIMARY KEY,
+    id TEXT NOT NULL,
+    make TEXT NOT NULL,
+    model TEXT NOT NULL,
+    year INTEGER,
+    row TEXT,
+    location TEXT,
+    store_id INTEGER,
+    store_name TEXT,
+    store_json TEXT,
+    image_urls TEXT NOT NULL DEFAULT '[]',
+    set_date TEXT,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    gone_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_vehicles_store ON vehicles(store_id);
+
+CREATE TABLE IF NOT EXISTS sightings (
+    vehicle_key TEXT NOT NULL REFERENCES vehicles(vehicle_key),
+    search_url TEXT NOT NULL,
+    seen_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_sightings_vehicle ON sightings(vehicle_key);
+CREATE INDEX IF NOT EXISTS idx_sightings_search ON sightings(search_url);
+";
+
+#[derive(Debug)]
+pub enum Error {
+    Database(rusqlite::Error),
+    Serialization(serde_json::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Database(e) => write!(f, "Database error: {}", e),
+            Error::Serialization(e) => write!(f, "Serialization error: {}", e),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Serialization(e)
+    }
+}
+
+/// What changed in a yard listing compared to the previous run of the same search.
+#[derive(Debug, Default)]
+pub struct ListingChanges {
+    /// Vehicles seen for the first time, or back after having disappeared
+    pub arrived: Vec<String>,
+    /// Vehicles the search listed before but no longer does
+    pub gone: Vec<String>,
+}
+
+/// A stored vehicle and when it disappeared, if it has.
+type ItemAndGoneAt = (JunkyardItem, Option<DateTime<Utc>>);
+
+/// SQLite-backed record of every vehicle seen in a yard listing.
+pub struct InventoryStore {
+    conn: Mutex<Connection>,
+}
+
+/// Key a vehicle is stored under. Parsed ids are only unique within a store, so
+/// the store id (or name, when the id is unknown) is prepended.
+pub fn vehicle_key(item: &JunkyardItem) -> String {
+    let store = match &item.store {
+        Some(Store { store_id: Some(id), .. }) => id.to_string(),
+        Some(store) => store.name.to_lowercase().replace(' ', "_"),
+        None => "unknown".to_string(),
+    };
+    format!("{}:{}", store, item.id)
+}
+
+/// What [`InventoryStore::vehicle_history`] found for an id.
+#[derive(Debug)]
+pub enum HistoryLookup {
+    Found(Box<VehicleHistory>),
+    NotFound,
+    /// A parsed vehicle id listed by several stores, with their vehicle keys
+    Ambiguous(Vec<String>),
+}
+
+impl InventoryStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Records the result of one run of `search_url`.
+    ///
+    /// Every listed vehicle is upserted and its `first_seen`/`last_seen` filled in on
+    /// `items`. Vehicles this search listed before but not now are marked as gone.
+    pub fn record_listing(
+        &self,
+        search_url: &str,
+        items: &mut [JunkyardItem],
+        seen_at: DateTime<Utc>,
+    ) -> Result<ListingChanges, Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut changes = ListingChanges::default();
+
+        for item in items.iter_mut() {
+            let key = vehicle_key(item);
+            let existing: Option<(DateTime<Utc>, Option<DateTime<Utc>>)> = tx
+                .query_row(
+                    "SELECT first_seen, gone_at FROM vehicles WHERE vehicle_key = ?1",
+                    params![key],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+
+            let first_seen = match existing {
+                Some((first_seen, gone_at)) => {
+                    if gone_at.is_some() {
+                        changes.arrived.push(key.clone());
+                    }
+                    first_seen
+                }
+                None => {
+                    changes.arrived.push(key.clone());
+                    seen_at
+                }
+            };
+
+            let store_json = item.store.as_ref().map(serde_json::to_string).transpose()?;
+            tx.execute(
+                "INSERT INTO vehicles (vehicle_key, id, make, model, year, row, location, store_id,
+                     store_name, store_json, image_urls, set_date, first_seen, last_seen, gone_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, NULL)
+                 ON CONFLICT(vehicle_key) DO UPDATE SET
+                     row = excluded.row,
+                     location = excluded.location,
+                     store_json = excluded.store_json,
+                     image_urls = excluded.image_urls,
+                     set_date = excluded.set_date,
+                     last_seen = excluded.last_seen,
+                     gone_at = NULL",
+                params![
+                    key,
+                    item.id,
+                    item.make,
+                    item.model,
+                    item.year,
+                    item.row,
+                    item.location,
+                    item.store.as_ref().and_then(|s| s.store_id),
+                    item.store.as_ref().map(|s| s.name.clone()),
+                    store_json,
+                    serde_json::to_string(&item.image_urls)?,
+                    item.added_date,
+                    first_seen,
+                    seen_at,
+                ],
+            )?;
+            tx.execute(
+                "INSERT INTO sightings (vehicle_key, search_url, seen_at) VALUES (?1, ?2, ?3)",
+                params![key, search_url, seen_at],
+            )?;
+
+            item.first_seen = Some(first_seen);
+            item.last_seen = Some(seen_at);
+            item.availability = true;
+        }
+
+        // Anything this search has listed before but did not list now is gone
+        {
+            let mut stmt = tx.prepare(
+                "SELECT vehicle_key FROM vehicles
+                 WHERE gone_at IS NULL AND last_seen < ?2
+                   AND vehicle_key IN (SELECT vehicle_key FROM sightings WHERE search_url = ?1)",
+            )?;
+            changes.gone = stmt
+                .query_map(params![search_url, seen_at], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()?;
+        }
+        for key in &changes.gone {
+            tx.execute(
+                "UPDATE vehicles SET gone_at = ?2 WHERE vehicle_key = ?1",
+                params![key, seen_at],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(changes)
+    }
+
+    /// Looks up a stored vehicle; `availability` is false once it has disappeared.
+    pub fn get_vehicle(&self, key: &str) -> Result<Option<JunkyardItem>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let item = conn
+            .query_row(
+                "SELECT id, make, model, year, row, location, store_json, image_urls,
+                        set_date, first_seen, last_seen, gone_at
+                 FROM vehicles WHERE vehicle_key = ?1",
+                params![key],
+                item_from_row,
+            )
+            .optional()?;
+        Ok(item)
+    }
+
+    /// A vehicle's stored record and every sighting of it, oldest first. `id` is a
+    /// vehicle key, or a parsed vehicle id when only one store has it.
+    pub fn vehicle_history(&self, id: &str) -> Result<HistoryLookup, Error> {
+        let conn = self.conn.lock().unwrap();
+        let keys: Vec<String> = conn
+            .prepare("SELECT vehicle_key FROM vehicles WHERE vehicle_key = ?1 OR id = ?1 ORDER BY vehicle_key")?
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        let key = match keys.as_slice() {
+            [] => return Ok(HistoryLookup::NotFound),
+            [key] => key.clone(),
+            _ if keys.iter().any(|key| key == id) => id.to_string(),
+            _ => return Ok(HistoryLookup::Ambiguous(keys)),
+        };
+
+        let (vehicle, gone_at): (JunkyardItem, Option<DateTime<Utc>>) = conn.query_row(
+            "SELECT id, make, model, year, row, location, store_json, image_urls,
+                    set_date, first_seen, last_seen, gone_at
+             FROM vehicles WHERE vehicle_key = ?1",
+            params![key],
+            |row| Ok((item_from_row(row)?, row.get(11)?)),
+        )?;
+        let sightings = conn
+            .prepare("SELECT seen_at, search_url FROM sightings WHERE vehicle_key = ?1 ORDER BY seen_at")?
+            .query_map(params![key], |row| {
+                Ok(Sighting {
+                    seen_at: row.get(0)?,
+                    search_url: row.get(1)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+
+        Ok(HistoryLookup::Found(Box::new(VehicleHistory {
+            days_in_yard: gone_at.zip(vehicle.added_date).map(|(gone_at, set_at)| days_between(set_at, gone_at)),
+            vehicle_key: key,
+            first_seen: vehicle.first_seen,
+            last_seen: vehicle.last_seen,
+            gone_at,
+            vehicle,
+            sightings,
+        })))
+    }
+
+    /// Vehicles a store set in its yard, and vehicles that disappeared from it, since `since`.
+    pub fn store_changes(&self, store_id: u32, since: DateTime<Utc>) -> Result<StoreInventoryChanges, Error> {
+        let conn = self.conn.lock().unwrap();
+        let select = |condition: &str, order: &str| -> Result<Vec<ItemAndGoneAt>, Error> {
+            let sql = format!(
+                "SELECT id, make, model, year, row, location, store_json, image_urls,
+                        set_date, first_seen, last_seen, gone_at
+                 FROM vehicles WHERE store_id = ?1 AND {} ORDER BY {} DESC",
+                condition, order
+            );
+            let items = conn
+                .prepare(&sql)?
+                .query_map(params![store_id, since], |row| Ok((item_from_row(row)?, row.get(11)?)))?
+                .collect::<Result<_, _>>()?;
+            Ok(items)
+        };
+
+        let set = select("set_date >= ?2", "set_date")?.into_iter().map(|(item, _)| item).collect();
+        let removed = select("gone_at >= ?2", "gone_at")?;
+        // How long the removed cars were in the yard, from their set date; cars whose
+        // set date couldn't be read are left out
+        let days: Vec<f64> = removed
+            .iter()
+            .filter_map(|(item, gone_at)| Some(days_between(item.added_date?, (*gone_at)?)))
+            .collect();
+        let average_days_in_yard = (!days.is_empty()).then(|| days.iter().sum::<f64>() / days.len() as f64);
+
+        Ok(StoreInventoryChanges {
+            store_id,
+            since,
+            set,
+            removed: removed.into_iter().map(|(item, _)| item).collect(),
+            average_days_in_yard,
+        })
+    }
+}
+
+fn days_between(from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+    let days = (to - from).num_minutes() as f64 / (24.0 * 60.0);
+    (days * 10.0).round() / 10.0
+}
+
+/// Builds a `JunkyardItem` from a row selected as in [`InventoryStore::get_vehicle`].
+fn item_from_row(row: &rusqlite::Row) -> rusqlite::Result<JunkyardItem> {
+    let store_json: Option<String> = row.get(6)?;
+    let image_urls: String = row.get(7)?;
+    let gone_at: Option<DateTime<Utc>> = row.get(11)?;
+
+    Ok(JunkyardItem {
+        id: row.get(0)?,
+        make: row.get(1)?,
+        model: row.get(2)?,
+        year: row.get(3)?,
+        row: row.get(4)?,
+        location: row.get(5)?,
+        store: store_json.and_then(|json| serde_json::from_str(&json).ok()),
+        image_urls: serde_json::from_str(&image_urls).unwrap_or_default(),
+        availability: gone_at.is_none(),
+        added_date: row.get(8)?,
+        first_seen: row.get(9)?,
+        last_seen: row.get(10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_junkyard_page;
+    use chrono::Duration;
+
+    #[test]
+    fn tracks_first_seen_and_disappearance() {
+        let inventory = InventoryStore::open_in_memory().unwrap();
+        let page = include_str!("../firecrawl_sample_output.md");
+        let url = "https://www.picknpull.com/check-inventory/vehicle-search?make=226&model=4154";
+        let day_one = Utc::now();
+
+        let mut items = parse_junkyard_page(page, url);
+        let changes = inventory.record_listing(url, &mut items, day_one).unwrap();
+        assert_eq!(changes.arrived, vec!["47:2005_subaru_impreza_wagon_132"]);
+
+        let mut items = parse_junkyard_page(page, url);
+        let changes = inventory.record_listing(url, &mut items, day_one + Duration::days(1)).unwrap();
+        assert!(changes.arrived.is_empty());
+        assert_eq!(items[0].first_seen, Some(day_one));
+
+        let changes = inventory.record_listing(url, &mut [], day_one + Duration::days(2)).unwrap();
+        assert_eq!(changes.gone, vec!["47:2005_subaru_impreza_wagon_132"]);
+
+        let stored = inventory.get_vehicle("47:2005_subaru_impreza_wagon_132").unwrap().unwrap();
+        assert!(!stored.availability);
+        assert_eq!(stored.store.unwrap().name, "Newark");
+    }
+
+    const URL: &str = "https://www.picknpull.com/check-inventory/vehicle-search?make=226&model=4154";
+
+    /// The sample page's Impreza, set 04/02/2025, seen on the 10th and the 11th
+    /// and gone on the 12th.
+    fn listed_then_gone(inventory: &InventoryStore, item: JunkyardItem) -> DateTime<Utc> {
+        let day_one = "2025-04-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        for day in 0..2 {
+            inventory.record_listing(URL, &mut [item.clone()], day_one + Duration::days(day)).unwrap();
+        }
+        inventory.record_listing(URL, &mut [], day_one + Duration::days(2)).unwrap();
+        day_one
+    }
+
+    fn sample_item() -> JunkyardItem {
+        parse_junkyard_page(include_str!("../firecrawl_sample_output.md"), URL).remove(0)
+    }
+
+    #[test]
+    fn reports_sightings_and_days_in_yard() {
+        let inventory = InventoryStore::open_in_memory().unwrap();
+        let day_one = listed_then_gone(&inventory, sample_item());
+
+        let HistoryLookup::Found(history) = inventory.vehicle_history("2005_subaru_impreza_wagon_132").unwrap() else {
+            panic!("a parsed id listed by one store finds its vehicle");
+        };
+        assert_eq!(history.vehicle_key, "47:2005_subaru_impreza_wagon_132");
+        assert_eq!(history.sightings.len(), 2);
+        assert_eq!(history.gone_at, Some(day_one + Duration::days(2)));
+        assert_eq!(history.days_in_yard, Some(10.0));
+        assert!(matches!(inventory.vehicle_history("1999_subaru_baja_1").unwrap(), HistoryLookup::NotFound));
+
+        let changes = inventory.store_changes(47, day_one).unwrap();
+        assert_eq!(changes.removed.len(), 1);
+        assert_eq!(changes.average_days_in_yard, Some(10.0));
+        // Set before `since`, so not among the new arrivals
+        assert!(changes.set.is_empty());
+        assert!(inventory.store_changes(52, day_one).unwrap().removed.is_empty());
+    }
+
+    #[test]
+    fn lists_every_store_for_an_ambiguous_id() {
+        let inventory = InventoryStore::open_in_memory().unwrap();
+        let newark = sample_item();
+        let mut oakland = newark.clone();
+        oakland.store.as_mut().unwrap().store_id = Some(41);
+        inventory.record_listing(URL, &mut [newark, oakland], Utc::now()).unwrap();
+
+        match inventory.vehicle_history("2005_subaru_impreza_wagon_132").unwrap() {
+            HistoryLookup::Ambiguous(keys) => assert_eq!(
+                keys,
+                ["41:2005_subaru_impreza_wagon_132", "47:2005_subaru_impreza_wagon_132"]
+            ),
+            other => panic!("expected the candidate keys, got {:?}", other),
+        }
+        let lookup = inventory.vehicle_history("41:2005_subaru_impreza_wagon_132").unwrap();
+        assert!(matches!(lookup, HistoryLookup::Found(history) if history.vehicle_key.starts_with("41:")));
+    }
+
+    #[test]
+    fn leaves_unread_set_dates_out_of_days_in_yard() {
+        let inventory = InventoryStore::open_in_memory().unwrap();
+        let day_one = listed_then_gone(&inventory, JunkyardItem { added_date: None, ..sample_item() });
+
+        let HistoryLookup::Found(history) = inventory.vehicle_history("47:2005_subaru_impreza_wagon_132").unwrap() else {
+            panic!("a vehicle key finds its vehicle");
+        };
+        assert!(history.gone_at.is_some());
+        assert_eq!(history.days_in_yard, None);
+
+        let changes = inventory.store_changes(47, day_one).unwrap();
+        assert_eq!(changes.removed.len(), 1);
+        assert_eq!(changes.average_days_in_yard, None);
+    }
+}
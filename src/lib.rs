@@ -1,8 +1,29 @@
+#![allow(non_snake_case)]
+
 pub mod api;
+pub mod catalog;
+pub mod catalog_sync;
+pub mod data_file;
+pub mod email;
+pub mod fetcher;
 pub mod firecrawl_client;
+pub mod geo;
+pub mod interchange;
+pub mod inventory;
 pub mod models;
+pub mod normalize;
+pub mod notifier;
 pub mod parser;
 pub mod pick_n_pull;
+pub mod scheduler;
+pub mod stores;
+pub mod trips;
+pub mod validation;
+pub mod watchlist;
+pub mod webhook;
+
+#[cfg(test)]
+mod test_support;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
@@ -1,27 +1,21 @@
 use junkyardTracker::firecrawl_client::FirecrawlClient;
-use junkyardTracker::parser::parse_junkyard_page;
-use std::env;
-
-mod pick_n_pull;
-use pick_n_pull::PicknPullSearch;
+use junkyardTracker::models::{ParserMode, SearchRadius};
+use junkyardTracker::parser::parse_page;
+use junkyardTracker::pick_n_pull::PicknPullSearch;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load .env file
     dotenv::dotenv().ok();
     
-    // Get API key from environment variable
-    let api_key = env::var("FIRECRAWL_API_KEY")
-        .expect("FIRECRAWL_API_KEY environment variable not set");
-
-    // Create FirecrawlClient
-    let client = FirecrawlClient::new(api_key);
+    // Create FirecrawlClient from the FIRECRAWL_* environment variables
+    let client = FirecrawlClient::from_env()?;
 
     // Create PicknPull search URL generator
     let pnp_search = PicknPullSearch::new();
 
     // Generate URL for Subaru Impreza Wagon search
-    let search_url = pnp_search.subaru_impreza_wagon_search("95014", 50);
+    let search_url = pnp_search.subaru_impreza_wagon_search("95014", SearchRadius::Miles50);
     println!("Searching URL: {}", search_url);
 
     // Crawl the webpage
@@ -31,12 +25,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Parse the response
     let items = if let Some(data) = &response.data {
-        if let Some(markdown) = &data.markdown {
-            parse_junkyard_page(markdown, &search_url)
-        } else {
-            println!("No markdown data in response");
-            Vec::new()
+        if data.markdown.is_none() && data.html.is_none() {
+            println!("No markdown or html data in response");
         }
+        parse_page(data.markdown.as_deref(), data.html.as_deref(), &search_url, ParserMode::Auto)
     } else {
         println!("No data in response");
         Vec::new()
@@ -45,12 +37,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Print the results
     println!("Found {} vehicles:", items.len());
     for item in items {
+        let distance = item.store.as_ref()
+            .and_then(|s| s.distance_miles)
+            .map(|d| format!(" ({} miles)", d))
+            .unwrap_or_default();
         println!(
-            "{} {} {} - Location: {}",
+            "{} {} {} - Location: {}{}",
             item.id,
             item.make,
             item.model,
-            item.location.as_deref().unwrap_or("Unknown")
+            item.location.as_deref().unwrap_or("Unknown"),
+            distance
         );
     }
 
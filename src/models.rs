@@ -1,24 +1,271 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::interchange::Part;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JunkyardItem {
     pub id: String,
     pub make: String,
     pub model: String,
     pub year: Option<u32>,
     pub location: Option<String>,
+    pub row: Option<String>,
+    pub store: Option<Store>,
+    #[serde(default)]
+    pub image_urls: Vec<String>,
     pub availability: bool,
-    pub added_date: chrono::DateTime<chrono::Utc>,
+    pub added_date: Option<chrono::DateTime<chrono::Utc>>, // The set date, when the page's could be read
+    pub first_seen: Option<chrono::DateTime<chrono::Utc>>, // Filled in once the item is persisted
+    pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Store {
+    pub store_id: Option<u32>, // From the store link, e.g. /locations/47/newark-ca
+    pub name: String,
+    pub url: Option<String>,
+    pub address: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub zip: Option<String>,
+    pub phone: Option<String>,
+    pub distance_miles: Option<f64>, // As reported by the site, e.g. "(Approx. 14.8 miles)"
+    /// Great-circle distance from the searched zip, from the bundled zip centroids
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geo_distance_miles: Option<f64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A yard from the site's "Locations" list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreLocation {
+    pub id: String, // Slug of the name, e.g. "newark" or "kansas-city-12th-st"
+    pub name: String,
+    pub state: String, // State or province, as listed
+    pub zip: String,
+    pub store_id: Option<u32>, // Only known once the store has shown up in search results
+    pub location_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchRequest {
     pub make: String,
-    pub model: String,
-    pub year_min: u32,
-    pub year_max: u32,
-    pub zip_code: String,
-    pub distance: Option<u32>, // Optional, defaults to 50 miles
+    /// One model, a list, or none for every model of the make
+    #[serde(rename = "model", alias = "models", default, deserialize_with = "one_or_many")]
+    pub models: Vec<String>,
+    pub year_min: Option<u32>, // Optional; leave out either end for an open range
+    pub year_max: Option<u32>,
+    #[serde(default)]
+    pub zip_code: String, // Not needed when searching a single store
+    pub distance: Option<SearchRadius>, // Optional, defaults to 50 miles
+    pub parser: Option<ParserMode>, // Optional, defaults to auto
+    /// Only this yard ("newark" or a store id), instead of a radius around `zip_code`
+    #[serde(default, alias = "store", skip_serializing_if = "Option::is_none")]
+    pub store_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortOrder>, // Optional, defaults to the site's order
+    /// Drop vehicles at yards further than this from `zip_code`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_miles: Option<f64>,
+}
+
+impl SearchRequest {
+    /// e.g. "Subaru Impreza/Outback 2002-2007", for messages about the search.
+    pub fn label(&self) -> String {
+        let mut label = self.make.trim().to_string();
+        if !self.models.is_empty() {
+            label = format!("{} {}", label, self.models.join("/"));
+        }
+        match (self.year_min, self.year_max) {
+            (Some(from), Some(to)) if from == to => format!("{} {}", label, from),
+            (Some(from), Some(to)) => format!("{} {}-{}", label, from, to),
+            (Some(from), None) => format!("{} {}+", label, from),
+            (None, Some(to)) => format!("{} -{}", label, to),
+            (None, None) => label,
+        }
+    }
+}
+
+/// How search results are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Closest yard first, by computed distance where known
+    Distance,
+    /// Most recently set vehicles first
+    Newest,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "distance" => Ok(SortOrder::Distance),
+            "newest" => Ok(SortOrder::Newest),
+            other => Err(format!("Unknown sort order: {}", other)),
+        }
+    }
+}
+
+/// Accepts `"Impreza"`, `["Impreza", "Outback"]` or `null`.
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Option::<OneOrMany>::deserialize(deserializer)? {
+        Some(OneOrMany::One(model)) => vec![model],
+        Some(OneOrMany::Many(models)) => models,
+        None => Vec::new(),
+    })
+}
+
+/// The search radii the Pick-n-Pull search form offers.
+///
+/// Serialized as the mileage, or `"unlimited"`. Any other mileage up to 500 is
+/// rounded up to the next radius the site offers, so 30 (or 25.5) searches 50
+/// miles. Anything further has to be asked for as `"unlimited"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchRadius {
+    Miles10,
+    Miles25,
+    #[default]
+    Miles50,
+    Miles100,
+    Miles250,
+    Miles500,
+    Unlimited,
+}
+
+impl SearchRadius {
+    pub const ALL: [SearchRadius; 7] = [
+        SearchRadius::Miles10,
+        SearchRadius::Miles25,
+        SearchRadius::Miles50,
+        SearchRadius::Miles100,
+        SearchRadius::Miles250,
+        SearchRadius::Miles500,
+        SearchRadius::Unlimited,
+    ];
+
+    /// The radius in miles; `None` when unlimited.
+    pub fn miles(self) -> Option<u32> {
+        match self {
+            SearchRadius::Miles10 => Some(10),
+            SearchRadius::Miles25 => Some(25),
+            SearchRadius::Miles50 => Some(50),
+            SearchRadius::Miles100 => Some(100),
+            SearchRadius::Miles250 => Some(250),
+            SearchRadius::Miles500 => Some(500),
+            SearchRadius::Unlimited => None,
+        }
+    }
+
+    /// The largest radius in miles; beyond it only unlimited is offered.
+    pub const MAX_MILES: u32 = 500;
+
+    /// The smallest radius covering `miles`; `None` for 0 or more than [`Self::MAX_MILES`].
+    pub fn from_miles(miles: u32) -> Option<Self> {
+        if miles == 0 {
+            return None;
+        }
+        Self::ALL
+            .into_iter()
+            .find(|radius| radius.miles().is_some_and(|limit| miles <= limit))
+    }
+
+    /// Like [`from_miles`](Self::from_miles), saying what's wrong with `miles`.
+    fn covering(miles: u32) -> Result<Self, String> {
+        Self::from_miles(miles).ok_or_else(|| match miles {
+            0 => "distance must be at least 1 mile".to_string(),
+            _ => format!(
+                "distance {} is over the {} mile limit, use 'unlimited' to search every yard",
+                miles,
+                Self::MAX_MILES
+            ),
+        })
+    }
+}
+
+impl std::fmt::Display for SearchRadius {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.miles() {
+            Some(miles) => write!(f, "{}", miles),
+            None => write!(f, "unlimited"),
+        }
+    }
+}
+
+impl std::str::FromStr for SearchRadius {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("unlimited") {
+            return Ok(SearchRadius::Unlimited);
+        }
+        let miles = s
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid distance '{}', expected miles or 'unlimited'", s))?;
+        SearchRadius::covering(miles)
+    }
+}
+
+impl Serialize for SearchRadius {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.miles() {
+            Some(miles) => serializer.serialize_u32(miles),
+            None => serializer.serialize_str("unlimited"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SearchRadius {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Miles(f64),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            // Fractions round up too; the cast takes negative mileages to 0
+            Raw::Miles(miles) => {
+                SearchRadius::covering(miles.ceil() as u32).map_err(serde::de::Error::custom)
+            }
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Which parser reads the crawled page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParserMode {
+    Markdown,
+    Html,
+    #[default]
+    Auto,
+}
+
+impl std::str::FromStr for ParserMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "markdown" => Ok(ParserMode::Markdown),
+            "html" => Ok(ParserMode::Html),
+            "auto" => Ok(ParserMode::Auto),
+            other => Err(format!("Unknown parser: {}", other)),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -27,10 +274,295 @@ pub struct SearchResponse {
     pub vehicles: Vec<JunkyardItem>,
     pub search_params: SearchRequest,
     pub total_found: usize,
+    /// Caveats about the results, e.g. that `zip_code` couldn't be located so
+    /// `geo_distance_miles` is missing and sorting uses the site's distances
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Donor vehicles for a part, found through the interchange data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartSearchRequest {
+    pub part: String, // Part name, alias or id, e.g. "2.5L EJ25 engine"
+    pub zip_code: String,
+    pub distance: Option<SearchRadius>,
+    pub parser: Option<ParserMode>,
+    #[serde(default)]
+    pub sort: Option<SortOrder>,
+    #[serde(default)]
+    pub max_miles: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PartSearchResponse {
+    pub success: bool,
+    pub query: String,
+    pub parts: Vec<PartResult>,
+    pub total_found: usize,
+    /// Fitment searches that failed and were left out, plus what every search
+    /// ran into once, e.g. a `zip_code` that couldn't be located
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Yard inventory for one matching part.
+#[derive(Debug, Serialize)]
+pub struct PartResult {
+    pub part: Part,
+    pub vehicles: Vec<JunkyardItem>,
+    pub total_found: usize,
+}
+
+/// A day out from `zip_code` to the yards that have what's wanted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TripPlanRequest {
+    pub zip_code: String, // Where the trip starts and ends
+    pub wanted: Vec<WantedItem>,
+    /// Longest round trip to plan, as the crow flies; no limit when left out
+    #[serde(default)]
+    pub max_total_miles: Option<f64>,
+    /// Search radius around `zip_code`; defaults to half of `max_total_miles`, else 50 miles
+    #[serde(default)]
+    pub distance: Option<SearchRadius>,
+    #[serde(default)]
+    pub parser: Option<ParserMode>,
+}
+
+impl TripPlanRequest {
+    /// The radius to search: `distance`, else far enough to reach any yard on a
+    /// trip within `max_total_miles`. A trip too long for the largest offered
+    /// radius searches every yard.
+    pub fn search_radius(&self) -> Option<SearchRadius> {
+        self.distance.or_else(|| {
+            let reach = (self.max_total_miles? / 2.0).ceil();
+            if reach > SearchRadius::MAX_MILES as f64 {
+                Some(SearchRadius::Unlimited)
+            } else {
+                SearchRadius::from_miles(reach as u32)
+            }
+        })
+    }
+}
+
+/// One thing to pick up: a vehicle (`make`, optional models and years) or a `part`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WantedItem {
+    /// How the item is named in the plan; defaults to the part or vehicle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub make: Option<String>,
+    #[serde(rename = "model", alias = "models", default, deserialize_with = "one_or_many")]
+    pub models: Vec<String>,
+    #[serde(default)]
+    pub year_min: Option<u32>,
+    #[serde(default)]
+    pub year_max: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<String>, // Part name, alias or id, as for /parts/search
+}
+
+impl WantedItem {
+    pub fn label(&self) -> String {
+        if let Some(label) = self.label.as_deref().filter(|label| !label.trim().is_empty()) {
+            return label.trim().to_string();
+        }
+        if let Some(part) = &self.part {
+            return part.trim().to_string();
+        }
+        self.search_request("", None, None).label()
+    }
+
+    /// The yard search for a wanted vehicle.
+    pub fn search_request(&self, zip_code: &str, distance: Option<SearchRadius>, parser: Option<ParserMode>) -> SearchRequest {
+        SearchRequest {
+            make: self.make.clone().unwrap_or_default(),
+            models: self.models.clone(),
+            year_min: self.year_min,
+            year_max: self.year_max,
+            zip_code: zip_code.to_string(),
+            distance,
+            parser,
+            store_id: None,
+            sort: None,
+            max_miles: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TripPlanResponse {
+    pub success: bool,
+    pub zip_code: String,
+    pub max_total_miles: Option<f64>,
+    #[serde(flatten)]
+    pub plan: TripPlan,
+    /// Searches that failed and were planned without, e.g. a donor model the
+    /// catalog doesn't know
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Yards to visit in order, and which wanted items the route does and doesn't cover.
+#[derive(Debug, Clone, Serialize)]
+pub struct TripPlan {
+    pub stops: Vec<TripStop>,
+    pub total_miles: f64, // Including the drive home
+    pub covered: Vec<String>,
+    pub not_covered: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TripStop {
+    pub store: Store,
+    pub leg_miles: f64, // From the previous stop, or from home
+    pub matches: Vec<TripMatch>,
+}
+
+/// Vehicles at one yard for one wanted item.
+#[derive(Debug, Clone, Serialize)]
+pub struct TripMatch {
+    pub wanted: usize, // Index into the request's `wanted`
+    pub label: String,
+    pub vehicles: Vec<JunkyardItem>,
+}
+
+/// A search saved to the watchlist so it can be re-run from the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub owner: String,
+    pub search: SearchRequest,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_run_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWatchRequest {
+    pub name: String,
+    pub owner: String,
+    pub search: SearchRequest,
+}
+
+/// Outcome of running one saved search.
+#[derive(Debug, Serialize)]
+pub struct WatchRunResult {
+    pub watch_id: i64,
+    pub name: String,
+    pub success: bool,
+    pub total_found: usize,
+    pub vehicles: Vec<JunkyardItem>,
+    pub new_arrivals: Vec<ArrivalEvent>,
+    pub error: Option<String>,
+}
+
+/// A vehicle that showed up in a saved search's results since its previous run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArrivalEvent {
+    pub id: i64,
+    pub watch_id: i64,
+    pub watch_name: String,
+    pub vehicle_key: String,
+    pub vehicle: JunkyardItem,
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Everything recorded about one vehicle: when it was first and last listed, each
+/// search that listed it, and when it disappeared.
+#[derive(Debug, Clone, Serialize)]
+pub struct VehicleHistory {
+    pub vehicle_key: String,
+    pub vehicle: JunkyardItem,
+    pub first_seen: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
+    pub gone_at: Option<chrono::DateTime<chrono::Utc>>, // None while it's still listed
+    pub days_in_yard: Option<f64>, // From its set date, when known, to its disappearance
+    pub sightings: Vec<Sighting>,
+}
+
+/// One search run that listed a vehicle.
+#[derive(Debug, Clone, Serialize)]
+pub struct Sighting {
+    pub seen_at: chrono::DateTime<chrono::Utc>,
+    pub search_url: String,
+}
+
+/// Cars set in, and removed from, one yard within a window.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreInventoryChanges {
+    pub store_id: u32,
+    pub since: chrono::DateTime<chrono::Utc>,
+    pub set: Vec<JunkyardItem>,
+    pub removed: Vec<JunkyardItem>,
+    /// Mean days from set date to removal of the removed cars
+    pub average_days_in_yard: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub success: bool,
     pub error: String,
+    /// Machine-readable error kind, e.g. "validation_failed"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// What is wrong with each rejected request field
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub field_errors: Vec<FieldError>,
+    /// "Did you mean" candidates when a make or model was not recognised
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+/// One problem with one field of a request.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String, // e.g. "required", "invalid_format", "out_of_range"
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl ErrorResponse {
+    pub fn new(error: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            error: error.into(),
+            code: None,
+            field_errors: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// A "validation_failed" error listing every rejected field.
+    pub fn validation(field_errors: Vec<FieldError>) -> Self {
+        let summary: Vec<String> = field_errors
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect();
+        Self {
+            code: Some("validation_failed".to_string()),
+            field_errors,
+            ..Self::new(format!("Invalid request: {}", summary.join("; ")))
+        }
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Vec<String>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+/// Other names people use for a make, as folded names. The first entry of each
+/// group is the usual catalog name, but any entry present in the catalog matches.
+const MAKE_ALIASES: &[&[&str]] = &[
+    &["volkswagen", "vw", "vdub"],
+    &["mercedes", "mercedesbenz", "benz", "mb"],
+    &["chevrolet", "chevy"],
+    &["landrover", "rangerover"],
+    &["alfaromeo", "alfa"],
+];
+
+/// Model aliases per (folded) make.
+const MODEL_ALIASES: &[(&str, &[&str])] = &[
+    ("subaru", &["imprezawagon", "imprezasportwagon"]),
+    ("volkswagen", &["golf", "rabbit"]),
+];
+
+/// Folds a make or model name for lookup: lowercase, with punctuation and
+/// whitespace dropped, so "CR-V", "crv" and "Cr V " are all "crv".
+pub fn fold(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Looks up a make in a map keyed by folded name, trying its aliases as well.
+pub fn resolve_make<'a, V>(map: &'a HashMap<String, V>, make: &str) -> Option<&'a V> {
+    resolve(map, &fold(make), MAKE_ALIASES.iter().copied())
+}
+
+/// Looks up a model of `make_key` (a folded make) in a map keyed by folded name.
+pub fn resolve_model<'a, V>(map: &'a HashMap<String, V>, make_key: &str, model: &str) -> Option<&'a V> {
+    let groups = MODEL_ALIASES
+        .iter()
+        .filter(|(make, _)| *make == make_key)
+        .map(|(_, group)| *group);
+    resolve(map, &fold(model), groups)
+}
+
+fn resolve<'a, 'g, V>(
+    map: &'a HashMap<String, V>,
+    key: &str,
+    mut groups: impl Iterator<Item = &'g [&'g str]>,
+) -> Option<&'a V> {
+    if let Some(value) = map.get(key) {
+        return Some(value);
+    }
+    let group = groups.find(|group| group.contains(&key))?;
+    group.iter().find_map(|alias| map.get(*alias))
+}
+
+/// Up to three names from `candidates` that look like a typo of `input`, closest first.
+pub fn suggest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let key = fold(input);
+    if key.is_empty() {
+        return Vec::new();
+    }
+    let max_distance = (key.chars().count() / 3).max(2);
+
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let folded = fold(candidate);
+            let distance = if folded.starts_with(&key) || key.starts_with(&folded) {
+                // Treat "imprez" -> "impreza" as close regardless of length
+                1
+            } else {
+                edit_distance(&key, &folded)
+            };
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored.into_iter().take(3).map(|(_, name)| name.to_string()).collect()
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_aliases_and_suggests() {
+        assert_eq!(fold(" Impreza  Wagon "), "imprezawagon");
+        assert_eq!(fold("CR-V"), fold("crv"));
+
+        let makes: HashMap<String, u32> = [("mercedes".to_string(), 159), ("volkswagen".to_string(), 273)].into();
+        assert_eq!(resolve_make(&makes, "Mercedes-Benz"), Some(&159));
+        assert_eq!(resolve_make(&makes, "VW"), Some(&273));
+        assert_eq!(resolve_make(&makes, "Saab"), None);
+
+        assert_eq!(suggest("Subru", ["Subaru", "Suzuki", "Toyota"]), vec!["Subaru"]);
+        assert_eq!(suggest("civc", ["Civic", "Accord"]), vec!["Civic"]);
+        assert!(suggest("Zzzzzz", ["Civic", "Accord"]).is_empty());
+    }
+}
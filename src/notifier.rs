@@ -0,0 +1,34 @@
+use async_trait::async_trait;
+use std::error::Error as StdError;
+use std::sync::Arc;
+
+use crate::models::ArrivalEvent;
+
+pub type NotifyError = Box<dyn StdError + Send + Sync>;
+
+/// Something that tells people about new vehicle arrivals.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Short name used in log lines, e.g. "webhook".
+    fn name(&self) -> &str;
+
+    async fn notify(&self, events: &[ArrivalEvent]) -> Result<(), NotifyError>;
+}
+
+/// Hands `events` to every notifier in the background so a slow or failing
+/// receiver never holds up a search run.
+pub fn dispatch(notifiers: &[Arc<dyn Notifier>], events: &[ArrivalEvent]) {
+    if events.is_empty() {
+        return;
+    }
+
+    for notifier in notifiers {
+        let notifier = notifier.clone();
+        let events = events.to_vec();
+        tokio::spawn(async move {
+            if let Err(e) = notifier.notify(&events).await {
+                println!("{} notification failed: {}", notifier.name(), e);
+            }
+        });
+    }
+}
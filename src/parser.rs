@@ -1,6 +1,45 @@
-use crate::models::JunkyardItem;
+use crate::models::{JunkyardItem, ParserMode, Store, StoreLocation};
 use regex::Regex;
 use chrono::{DateTime, Utc};
+use scraper::{ElementRef, Html, Selector};
+
+const PICKNPULL_ORIGIN: &str = "https://www.picknpull.com";
+
+/// Parses a crawled page with the parser selected by `mode`.
+///
+/// `Auto` prefers the HTML table walk when HTML is available and falls back to
+/// the markdown parser if that finds nothing.
+pub fn parse_page(markdown: Option<&str>, html: Option<&str>, source_url: &str, mode: ParserMode)
+    -> Vec<JunkyardItem> {
+    let from_markdown = || markdown.map(|m| parse_junkyard_page(m, source_url)).unwrap_or_default();
+    let from_html = || html.map(|h| parse_junkyard_html(h, source_url)).unwrap_or_default();
+
+    match mode {
+        ParserMode::Markdown => from_markdown(),
+        ParserMode::Html => from_html(),
+        ParserMode::Auto => {
+            let items = from_html();
+            if items.is_empty() {
+                from_markdown()
+            } else {
+                items
+            }
+        }
+    }
+}
+
+/// Whether the text the parser reads for `mode` is a search results page, with a
+/// "Matching Vehicles" or "No Vehicles Found" heading. An error page, or a page
+/// missing the format the parser needs, parses to nothing without meaning the yard
+/// is empty.
+pub fn is_results_page(markdown: Option<&str>, html: Option<&str>, mode: ParserMode) -> bool {
+    let recognised = |text: &str| text.contains("Matching Vehicles") || text.contains("No Vehicles Found");
+    match mode {
+        ParserMode::Markdown => markdown.is_some_and(recognised),
+        ParserMode::Html => html.is_some_and(recognised),
+        ParserMode::Auto => markdown.is_some_and(recognised) || html.is_some_and(recognised),
+    }
+}
 
 pub fn parse_junkyard_page(markdown: &str, source_url: &str) -> Vec<JunkyardItem> {
     let mut items = Vec::new();
@@ -15,47 +54,14 @@ pub fn parse_junkyard_page(markdown: &str, source_url: &str) -> Vec<JunkyardItem
     if let Some(matching_section_start) = markdown.find("## Matching Vehicles") {
         let matching_section = &markdown[matching_section_start..];
         
-        // Look for the table with vehicle data
-        // The table has columns: Photo | Year | Make | Model | Row | Set Date
-        let table_regex = Regex::new(r"\|\s*([^|]+)\s*\|\s*(\d{4})\s*\|\s*([^|]+)\s*\|\s*([^|]+)\s*\|\s*([^|]+)\s*\|\s*([^|]+)\s*\|").unwrap();
-        
-        for cap in table_regex.captures_iter(matching_section) {
-            // Skip the header row
-            if cap.get(2).map_or(false, |m| m.as_str() == "Year") {
-                continue;
-            }
-            
-            let year = cap.get(2).map_or("", |m| m.as_str().trim());
-            let make = cap.get(3).map_or("", |m| m.as_str().trim());
-            let model = cap.get(4).map_or("", |m| m.as_str().trim());
-            let row = cap.get(5).map_or("", |m| m.as_str().trim());
-            let set_date = cap.get(6).map_or("", |m| m.as_str().trim());
-            
-            // Skip if essential fields are empty
-            if year.is_empty() || make.is_empty() || model.is_empty() {
-                continue;
-            }
-            
-            // Extract location from the markdown (look for store name and address)
-            let location = extract_location_from_markdown(matching_section);
-            
-            // Create unique ID
-            let id = format!("{}_{}_{}_{}", 
-                year, 
-                make.to_lowercase().replace(' ', "_"), 
-                model.to_lowercase().replace(' ', "_"),
-                row.replace(' ', "_")
-            );
-            
-            items.push(JunkyardItem {
-                id,
-                make: make.to_string(),
-                model: model.to_string(),
-                year: year.parse().ok(),
-                location: Some(format!("Row {}, {}", row, location.unwrap_or("Unknown Location".to_string()))),
-                availability: true,
-                added_date: parse_set_date(set_date).unwrap_or_else(|| Utc::now()),
-            });
+        // A wide search lists one table per store, so attribute each row to the
+        // store header it appears under rather than to the first store on the page
+        for block in split_store_blocks(matching_section) {
+            let store = parse_store_header(block);
+            let location = store.as_ref()
+                .map(|s| s.name.clone())
+                .or_else(|| extract_location_from_markdown(block));
+            items.extend(parse_vehicle_table(block, location.as_deref(), store.as_ref()));
         }
     }
     
@@ -67,15 +73,160 @@ pub fn parse_junkyard_page(markdown: &str, source_url: &str) -> Vec<JunkyardItem
     items
 }
 
+/// Splits the "Matching Vehicles" section into one block per store.
+///
+/// Each block starts at a `[Pick-n-Pull - <Store>](...)` header and runs until the
+/// next one. Any text before the first header is returned as its own block.
+fn split_store_blocks(section: &str) -> Vec<&str> {
+    let header_regex = Regex::new(r"\[Pick-n-Pull - [^\]]+\]\(").unwrap();
+    let starts: Vec<usize> = header_regex.find_iter(section).map(|m| m.start()).collect();
+
+    if starts.is_empty() {
+        return vec![section];
+    }
+
+    let mut blocks = Vec::new();
+    if !section[..starts[0]].trim().is_empty() {
+        blocks.push(&section[..starts[0]]);
+    }
+
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(section.len());
+        blocks.push(&section[start..end]);
+    }
+
+    blocks
+}
+
+/// Reads the store header at the top of a block: name, location page link,
+/// approximate distance, street address, city/state/zip and phone number.
+fn parse_store_header(block: &str) -> Option<Store> {
+    let header_regex = Regex::new(
+        r"\[Pick-n-Pull - ([^\]]+)\]\(([^)]+)\)(?:\s*\(Approx\.\s*([\d.]+)\s*miles?\))?"
+    ).unwrap();
+    let cap = header_regex.captures(block)?;
+
+    let name = cap.get(1)?.as_str().trim().to_string();
+    let url = cap.get(2).map(|m| m.as_str().trim().to_string());
+    let distance_miles = cap.get(3).and_then(|m| m.as_str().parse().ok());
+
+    let store_id = url.as_deref().and_then(store_id_from_url);
+    let (address, city, state, zip) = parse_store_address(block);
+
+    let phone_regex = Regex::new(r"\(tel:([^)]+)\)").unwrap();
+    let phone = phone_regex.captures(block)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().trim().to_string());
+
+    Some(Store {
+        store_id,
+        name,
+        url,
+        address,
+        city,
+        state,
+        zip,
+        phone,
+        distance_miles,
+        geo_distance_miles: None,
+    })
+}
+
+fn store_id_from_url(url: &str) -> Option<u32> {
+    let id_regex = Regex::new(r"/locations/(\d+)").unwrap();
+    id_regex.captures(url)?.get(1)?.as_str().parse().ok()
+}
+
+type StoreAddress = (Option<String>, Option<String>, Option<String>, Option<String>);
+
+/// Finds the "street • city, ST zip" line and splits it into its parts.
+fn parse_store_address(text: &str) -> StoreAddress {
+    // e.g. "7400 Mowry Avenue •  Newark, CA 94560[510-742-2277](tel:510-742-2277)"
+    let address_regex = Regex::new(
+        r"(?m)^\s*([^•\n\[]+?)\s*•\s*([^,\n]+),\s*([A-Za-z]{2})\s+([A-Za-z0-9][A-Za-z0-9 ]*?)\s*(?:\[|$)"
+    ).unwrap();
+    match address_regex.captures(text) {
+        Some(addr) => (
+            addr.get(1).map(|m| m.as_str().trim().to_string()),
+            addr.get(2).map(|m| m.as_str().trim().to_string()),
+            addr.get(3).map(|m| m.as_str().trim().to_string()),
+            addr.get(4).map(|m| m.as_str().trim().to_string()),
+        ),
+        None => (None, None, None, None),
+    }
+}
+
+fn parse_vehicle_table(block: &str, location: Option<&str>, store: Option<&Store>) -> Vec<JunkyardItem> {
+    let mut items = Vec::new();
+
+    // Look for the table with vehicle data
+    // The table has columns: Photo | Year | Make | Model | Row | Set Date
+    let table_regex = Regex::new(r"\|\s*([^|]+)\s*\|\s*(\d{4})\s*\|\s*([^|]+)\s*\|\s*([^|]+)\s*\|\s*([^|]+)\s*\|\s*([^|]+)\s*\|").unwrap();
+    
+    for cap in table_regex.captures_iter(block) {
+        // Skip the header row
+        if cap.get(2).is_some_and(|m| m.as_str() == "Year") {
+            continue;
+        }
+        
+        let photo = cap.get(1).map_or("", |m| m.as_str().trim());
+        let year = cap.get(2).map_or("", |m| m.as_str().trim());
+        let make = cap.get(3).map_or("", |m| m.as_str().trim());
+        let model = cap.get(4).map_or("", |m| m.as_str().trim());
+        let row = cap.get(5).map_or("", |m| m.as_str().trim());
+        let set_date = cap.get(6).map_or("", |m| m.as_str().trim());
+        
+        // Skip if essential fields are empty
+        if year.is_empty() || make.is_empty() || model.is_empty() {
+            continue;
+        }
+        
+        // Create unique ID
+        let id = format!("{}_{}_{}_{}", 
+            year, 
+            make.to_lowercase().replace(' ', "_"), 
+            model.to_lowercase().replace(' ', "_"),
+            row.replace(' ', "_")
+        );
+        
+        items.push(JunkyardItem {
+            id,
+            make: make.to_string(),
+            model: model.to_string(),
+            year: year.parse().ok(),
+            location: Some(format!("Row {}, {}", row, location.unwrap_or("Unknown Location"))),
+            row: Some(row.to_string()).filter(|r| !r.is_empty()),
+            store: store.cloned(),
+            image_urls: extract_image_urls(photo),
+            availability: true,
+            added_date: parse_set_date(set_date),
+            first_seen: None,
+            last_seen: None,
+        });
+    }
+
+    items
+}
+
+/// Pulls the image links out of the Photo cell, e.g.
+/// `![2005 Subaru Impreza Wagon](https://cdn.row52.com/images/....JPG)`.
+fn extract_image_urls(photo_cell: &str) -> Vec<String> {
+    let image_regex = Regex::new(r"!\[[^\]]*\]\(\s*([^)\s]+)").unwrap();
+    image_regex.captures_iter(photo_cell)
+        .filter_map(|cap| cap.get(1))
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
 fn extract_location_from_markdown(markdown: &str) -> Option<String> {
     // Look for store name pattern like "Pick-n-Pull - Newark"
-    let store_regex = Regex::new(r"Pick-n-Pull - ([^]]+)").unwrap();
+    let store_regex = Regex::new(r"Pick-n-Pull - ([^\]]+)").unwrap();
     if let Some(cap) = store_regex.captures(markdown) {
         return Some(cap.get(1)?.as_str().to_string());
     }
     
     // Look for address pattern
-    let address_regex = Regex::new(r"(\d+\s+[^•]+)•\s*([^[]+)").unwrap();
+    let address_regex = Regex::new(r"(\d+\s+[^•]+)•\s*([^\[]+)").unwrap();
     if let Some(cap) = address_regex.captures(markdown) {
         let address = cap.get(1)?.as_str().trim();
         let city_state = cap.get(2)?.as_str().trim();
@@ -128,10 +279,474 @@ fn parse_alternative_format(markdown: &str, _source_url: &str) -> Vec<JunkyardIt
             model: model.to_string(),
             year: year.parse().ok(),
             location: Some(format!("Row {}, {}", row, location.unwrap_or("Unknown Location".to_string()))),
+            row: Some(row.to_string()),
+            store: None,
+            image_urls: Vec::new(),
             availability: true,
-            added_date: parse_set_date(set_date).unwrap_or_else(|| Utc::now()),
+            added_date: parse_set_date(set_date),
+            first_seen: None,
+            last_seen: None,
         });
     }
     
     items
-}
\ No newline at end of file
+}
+
+/// Parses the inventory page from its HTML by walking the results tables.
+///
+/// Store headers (`<a href="/locations/47/newark-ca">Pick-n-Pull - Newark</a>`) and
+/// tables are visited in document order, so each table is attributed to the
+/// store header that precedes it.
+pub fn parse_junkyard_html(html: &str, _source_url: &str) -> Vec<JunkyardItem> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href*='/locations/'], table").unwrap();
+    let nested_table = Selector::parse("table").unwrap();
+
+    let mut items = Vec::new();
+    let mut current_store: Option<Store> = None;
+
+    for element in document.select(&selector) {
+        if element.value().name() == "a" {
+            if let Some(store) = parse_store_link(element) {
+                current_store = Some(store);
+            }
+            continue;
+        }
+
+        // Results are sometimes wrapped in a layout table; only read the innermost ones
+        if element.select(&nested_table).next().is_some() {
+            continue;
+        }
+
+        items.extend(parse_vehicle_table_html(element, current_store.as_ref()));
+    }
+
+    items
+}
+
+fn parse_store_link(link: ElementRef) -> Option<Store> {
+    let text = link.text().collect::<String>();
+    let name = text.trim().strip_prefix("Pick-n-Pull - ")?.trim().to_string();
+
+    let href = link.value().attr("href")?;
+    let url = if href.starts_with('/') {
+        format!("{}{}", PICKNPULL_ORIGIN, href)
+    } else {
+        href.to_string()
+    };
+
+    // The distance, address and phone live in the element wrapping the store link
+    let tel_selector = Selector::parse("a[href^='tel:']").unwrap();
+    let store_selector = Selector::parse("a[href*='/locations/']").unwrap();
+    let container = link.ancestors()
+        .filter_map(ElementRef::wrap)
+        .take(3)
+        .take_while(|el| el.select(&store_selector).count() <= 1)
+        .find(|el| el.select(&tel_selector).next().is_some());
+
+    let mut store = Store {
+        store_id: store_id_from_url(&url),
+        name,
+        url: Some(url),
+        address: None,
+        city: None,
+        state: None,
+        zip: None,
+        phone: None,
+        distance_miles: None,
+        geo_distance_miles: None,
+    };
+
+    if let Some(container) = container {
+        let container_text = container.text().collect::<Vec<_>>().join("\n");
+
+        let distance_regex = Regex::new(r"Approx\.\s*([\d.]+)\s*miles?").unwrap();
+        store.distance_miles = distance_regex.captures(&container_text)
+            .and_then(|c| c.get(1))
+            .and_then(|m| m.as_str().parse().ok());
+
+        let (address, city, state, zip) = parse_store_address(&container_text);
+        store.address = address;
+        store.city = city;
+        store.state = state;
+        store.zip = zip;
+
+        store.phone = container.select(&tel_selector).next()
+            .and_then(|a| a.value().attr("href"))
+            .and_then(|href| href.strip_prefix("tel:"))
+            .map(|p| p.trim().to_string());
+    }
+
+    Some(store)
+}
+
+fn parse_vehicle_table_html(table: ElementRef, store: Option<&Store>) -> Vec<JunkyardItem> {
+    let row_selector = Selector::parse("tr").unwrap();
+    let header_selector = Selector::parse("th").unwrap();
+    let cell_selector = Selector::parse("td").unwrap();
+    let image_selector = Selector::parse("img").unwrap();
+
+    // Map column names to positions, defaulting to the site's usual layout
+    let mut columns: Vec<String> = table.select(&header_selector)
+        .map(|th| th.text().collect::<String>().trim().to_lowercase())
+        .collect();
+    if columns.is_empty() {
+        columns = ["photo", "year", "make", "model", "row", "set date"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+    }
+    let column = |name: &str| columns.iter().position(|c| c == name);
+    let (Some(year_col), Some(make_col), Some(model_col)) =
+        (column("year"), column("make"), column("model")) else {
+        return Vec::new();
+    };
+    let photo_col = column("photo");
+    let row_col = column("row");
+    let date_col = column("set date");
+
+    let location = store.map(|s| s.name.as_str()).unwrap_or("Unknown Location");
+    let mut items = Vec::new();
+
+    for tr in table.select(&row_selector) {
+        let cells: Vec<ElementRef> = tr.select(&cell_selector).collect();
+        let cell_text = |idx: Option<usize>| -> String {
+            idx.and_then(|i| cells.get(i))
+                .map(|c| c.text().collect::<String>().trim().to_string())
+                .unwrap_or_default()
+        };
+
+        let year = cell_text(Some(year_col));
+        let make = cell_text(Some(make_col));
+        let model = cell_text(Some(model_col));
+        let row = cell_text(row_col);
+        let set_date = cell_text(date_col);
+
+        // Skip header and spacer rows
+        if year.len() != 4 || !year.chars().all(|c| c.is_ascii_digit()) || make.is_empty() || model.is_empty() {
+            continue;
+        }
+
+        let image_urls = photo_col
+            .and_then(|i| cells.get(i))
+            .map(|cell| {
+                cell.select(&image_selector)
+                    .filter_map(|img| img.value().attr("src"))
+                    .map(|src| src.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let id = format!("{}_{}_{}_{}",
+            year,
+            make.to_lowercase().replace(' ', "_"),
+            model.to_lowercase().replace(' ', "_"),
+            row.replace(' ', "_")
+        );
+
+        items.push(JunkyardItem {
+            id,
+            year: year.parse().ok(),
+            location: Some(format!("Row {}, {}", row, location)),
+            row: Some(row).filter(|r| !r.is_empty()),
+            store: store.cloned(),
+            image_urls,
+            availability: true,
+            added_date: parse_set_date(&set_date),
+            first_seen: None,
+            last_seen: None,
+            make,
+            model,
+        });
+    }
+
+    items
+}
+
+/// Reads the "Locations" list of every yard, grouped by state or province, from
+/// the markdown or, failing that, the HTML of a crawled page.
+///
+/// The page lists the yards twice (mobile and desktop menus); duplicates are dropped.
+pub fn parse_locations(markdown: Option<&str>, html: Option<&str>) -> Vec<StoreLocation> {
+    let locations = markdown.map(parse_locations_markdown).unwrap_or_default();
+    if locations.is_empty() {
+        html.map(parse_locations_html).unwrap_or_default()
+    } else {
+        locations
+    }
+}
+
+fn parse_locations_markdown(markdown: &str) -> Vec<StoreLocation> {
+    let Some(start) = markdown.find("#### Locations") else {
+        return Vec::new();
+    };
+    let section = &markdown[start + "#### Locations".len()..];
+    let section = section.find("\n## ").map_or(section, |end| &section[..end]);
+
+    let link_regex = Regex::new(r"^- \[([^\]]+)\]\(([^)]+)\)").unwrap();
+    let mut entries = Vec::new();
+    let mut state: Option<String> = None;
+    for line in section.lines().map(str::trim) {
+        if let Some(heading) = line.strip_prefix("#### ") {
+            state = Some(heading.trim().to_string());
+        } else if let (Some(state), Some(captures)) = (&state, link_regex.captures(line)) {
+            entries.push((captures[1].trim().to_string(), state.clone(), captures[2].to_string()));
+        }
+    }
+    build_locations(entries)
+}
+
+fn parse_locations_html(html: &str) -> Vec<StoreLocation> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("h4, a[href*='zip=']").unwrap();
+
+    let mut entries = Vec::new();
+    let mut in_locations = false;
+    let mut state: Option<String> = None;
+    for element in document.select(&selector) {
+        let text = element.text().collect::<String>().trim().to_string();
+        if element.value().name() == "h4" {
+            if text == "Locations" {
+                in_locations = true;
+            } else if in_locations {
+                state = Some(text);
+            }
+        } else if let (true, Some(state), Some(href)) = (in_locations, &state, element.value().attr("href")) {
+            entries.push((text, state.clone(), href.to_string()));
+        }
+    }
+    build_locations(entries)
+}
+
+/// Turns (name, state, search link) entries into locations with unique ids.
+fn build_locations(entries: Vec<(String, String, String)>) -> Vec<StoreLocation> {
+    let mut locations: Vec<StoreLocation> = Vec::new();
+    for (name, state, link) in entries {
+        let Some(zip) = zip_from_url(&link) else {
+            continue;
+        };
+        if locations.iter().any(|l| l.name == name && l.zip == zip) {
+            continue;
+        }
+
+        // Names are unique in practice; fall back to the state when one repeats
+        let mut id = slugify(&name);
+        if locations.iter().any(|l| l.id == id) {
+            id = format!("{}-{}", id, slugify(&state));
+        }
+        locations.push(StoreLocation {
+            id,
+            name,
+            state,
+            zip,
+            store_id: None,
+            location_url: None,
+        });
+    }
+    locations
+}
+
+/// The `zip` query parameter of a search link, e.g. "94560" or "T2B 1N2".
+fn zip_from_url(url: &str) -> Option<String> {
+    let query = url.split_once('?')?.1;
+    let value = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("zip="))?
+        .replace("%20", " ")
+        .replace('+', " ");
+    let value = value.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_uppercase())
+    }
+}
+
+/// Drops vehicles that aren't at `location`, e.g. from other yards within the
+/// search radius or that couldn't be attributed to a yard at all.
+pub fn retain_store(items: &mut Vec<JunkyardItem>, location: &StoreLocation) {
+    items.retain(|item| item.store.as_ref().is_some_and(|store| is_same_store(store, location)));
+}
+
+fn is_same_store(store: &Store, location: &StoreLocation) -> bool {
+    if let (Some(id), Some(location_id)) = (store.store_id, location.store_id) {
+        return id == location_id;
+    }
+    let postal = |zip: &str| zip.replace(' ', "").to_uppercase();
+    match &store.zip {
+        Some(zip) => postal(zip) == postal(&location.zip),
+        None => slugify(&store.name) == location.id,
+    }
+}
+
+/// "Kansas City (12th St)" -> "kansas-city-12th-st"
+pub fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_PAGE: &str = include_str!("../firecrawl_sample_output.md");
+
+    const MULTI_STORE_PAGE: &str = r#"## Matching Vehicles
+
+Part availability, vehicle condition and position in the yard may vary as our inventory changes daily.
+
+[Pick-n-Pull - Newark](https://www.picknpull.com/locations/47/newark-ca)(Approx. 14.8 miles)
+
+7400 Mowry Avenue •  Newark, CA 94560[510-742-2277](tel:510-742-2277)Store Layout Map\|Part Pricing
+
+| Photo | Year | Make | Model | Row | Set Date |
+| --- | --- | --- | --- | --- | --- |
+| ![2005 Subaru Impreza Wagon](https://cdn.row52.com/images/a.JPG) | 2005 | Subaru | Impreza Wagon | 132 | 04/02/2025 | 2005 Subaru Impreza Wagon<br>Row 132<br>Set: 04/02/2025 |
+
+[Pick-n-Pull - Oakland](https://www.picknpull.com/locations/41/oakland-ca)(Approx. 31.2 miles)
+
+8225 Baldwin Street •  Oakland, CA 94621[510-632-4544](tel:510-632-4544)Store Layout Map\|Part Pricing
+
+| Photo | Year | Make | Model | Row | Set Date |
+| --- | --- | --- | --- | --- | --- |
+| ![2003 Subaru Impreza Wagon](https://cdn.row52.com/images/b.JPG) | 2003 | Subaru | Impreza Wagon | 18 | 03/28/2025 | 2003 Subaru Impreza Wagon<br>Row 18<br>Set: 03/28/2025 |
+| ![2006 Subaru Impreza Wagon](https://cdn.row52.com/images/c.JPG) | 2006 | Subaru | Impreza Wagon | 22 | 04/05/2025 | 2006 Subaru Impreza Wagon<br>Row 22<br>Set: 04/05/2025 |
+
+Displaying 3 vehicles
+"#;
+
+    #[test]
+    fn parses_single_store_sample() {
+        let items = parse_junkyard_page(SAMPLE_PAGE, "");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "2005_subaru_impreza_wagon_132");
+        assert_eq!(items[0].location.as_deref(), Some("Row 132, Newark"));
+    }
+
+    #[test]
+    fn leaves_unreadable_set_dates_empty() {
+        assert!(parse_junkyard_page(SAMPLE_PAGE, "")[0].added_date.is_some());
+        let page = SAMPLE_PAGE.replace("04/02/2025", "TBD");
+        assert_eq!(parse_junkyard_page(&page, "")[0].added_date, None);
+    }
+
+    #[test]
+    fn attributes_rows_to_their_own_store() {
+        let items = parse_junkyard_page(MULTI_STORE_PAGE, "");
+        let locations: Vec<_> = items.iter().map(|i| i.location.as_deref().unwrap()).collect();
+        assert_eq!(locations, vec!["Row 132, Newark", "Row 18, Oakland", "Row 22, Oakland"]);
+    }
+
+    #[test]
+    fn populates_store_details_and_row() {
+        let items = parse_junkyard_page(SAMPLE_PAGE, "");
+        assert_eq!(items[0].row.as_deref(), Some("132"));
+
+        let store = items[0].store.as_ref().expect("store should be parsed");
+        assert_eq!(store.store_id, Some(47));
+        assert_eq!(store.name, "Newark");
+        assert_eq!(store.url.as_deref(), Some("https://www.picknpull.com/locations/47/newark-ca"));
+        assert_eq!(store.address.as_deref(), Some("7400 Mowry Avenue"));
+        assert_eq!(store.city.as_deref(), Some("Newark"));
+        assert_eq!(store.state.as_deref(), Some("CA"));
+        assert_eq!(store.zip.as_deref(), Some("94560"));
+        assert_eq!(store.phone.as_deref(), Some("510-742-2277"));
+        assert_eq!(store.distance_miles, Some(14.8));
+    }
+
+    const HTML_PAGE: &str = r#"<html><body>
+<h2>Matching Vehicles</h2>
+<table><tr><td>
+  <div class="store">
+    <h3><a href="/locations/47/newark-ca">Pick-n-Pull - Newark</a> <span>(Approx. 14.8 miles)</span></h3>
+    <p>7400 Mowry Avenue • Newark, CA 94560</p>
+    <a href="tel:510-742-2277">510-742-2277</a>
+  </div>
+  <table>
+    <thead><tr><th>Photo</th><th>Year</th><th>Make</th><th>Model</th><th>Row</th><th>Set Date</th></tr></thead>
+    <tbody>
+      <tr><td><img src="https://cdn.row52.com/images/a.JPG"></td><td>2005</td><td>Subaru</td><td>Impreza Wagon</td><td>132</td><td>04/02/2025</td></tr>
+    </tbody>
+  </table>
+  <div class="store">
+    <h3><a href="/locations/41/oakland-ca">Pick-n-Pull - Oakland</a> <span>(Approx. 31.2 miles)</span></h3>
+    <p>8225 Baldwin Street • Oakland, CA 94621</p>
+    <a href="tel:510-632-4544">510-632-4544</a>
+  </div>
+  <table>
+    <thead><tr><th>Photo</th><th>Year</th><th>Make</th><th>Model</th><th>Row</th><th>Set Date</th></tr></thead>
+    <tbody>
+      <tr><td><img src="https://cdn.row52.com/images/b.JPG"></td><td>2003</td><td>Subaru</td><td>Impreza Wagon</td><td>18</td><td>03/28/2025</td></tr>
+    </tbody>
+  </table>
+</td></tr></table>
+</body></html>"#;
+
+    #[test]
+    fn parses_html_tables_per_store() {
+        let items = parse_junkyard_html(HTML_PAGE, "");
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].id, "2005_subaru_impreza_wagon_132");
+        assert_eq!(items[0].image_urls, vec!["https://cdn.row52.com/images/a.JPG"]);
+        let newark = items[0].store.as_ref().unwrap();
+        assert_eq!(newark.store_id, Some(47));
+        assert_eq!(newark.url.as_deref(), Some("https://www.picknpull.com/locations/47/newark-ca"));
+        assert_eq!(newark.zip.as_deref(), Some("94560"));
+        assert_eq!(newark.phone.as_deref(), Some("510-742-2277"));
+        assert_eq!(newark.distance_miles, Some(14.8));
+
+        assert_eq!(items[1].location.as_deref(), Some("Row 18, Oakland"));
+        assert_eq!(items[1].store.as_ref().unwrap().store_id, Some(41));
+    }
+
+    #[test]
+    fn auto_mode_falls_back_to_markdown() {
+        let items = parse_page(Some(SAMPLE_PAGE), Some("<html></html>"), "", ParserMode::Auto);
+        assert_eq!(items.len(), 1);
+
+        let items = parse_page(Some(SAMPLE_PAGE), Some(HTML_PAGE), "", ParserMode::Auto);
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn captures_photo_urls() {
+        let items = parse_junkyard_page(SAMPLE_PAGE, "");
+        assert_eq!(
+            items[0].image_urls,
+            vec!["https://cdn.row52.com/images/b5871903-e24f-421d-9a4c-86c41e7b18d0.JPG"]
+        );
+    }
+
+    #[test]
+    fn parses_locations_list_once() {
+        let locations = parse_locations(Some(SAMPLE_PAGE), None);
+        assert_eq!(locations.len(), 50);
+
+        let newark = locations.iter().find(|l| l.id == "newark").unwrap();
+        assert_eq!((newark.state.as_str(), newark.zip.as_str()), ("California", "94560"));
+        let calgary = locations.iter().find(|l| l.id == "calgary-52-st").unwrap();
+        assert_eq!((calgary.state.as_str(), calgary.zip.as_str()), ("Alberta", "T2B 1N2"));
+        assert!(locations.iter().all(|l| l.state != "Locations"));
+    }
+
+    #[test]
+    fn keeps_only_the_searched_store() {
+        let locations = parse_locations(Some(SAMPLE_PAGE), None);
+        let oakland = locations.iter().find(|l| l.id == "oakland").unwrap();
+        let retained = |location: &StoreLocation| {
+            let mut items = parse_junkyard_page(MULTI_STORE_PAGE, "");
+            items.push(JunkyardItem { store: None, ..items[0].clone() });
+            retain_store(&mut items, location);
+            items.into_iter().map(|item| item.location.unwrap()).collect::<Vec<_>>()
+        };
+
+        // Matched by zip until the store id is known, then by id alone
+        assert_eq!(retained(oakland), ["Row 18, Oakland", "Row 22, Oakland"]);
+        let known = StoreLocation { store_id: Some(47), zip: "00000".to_string(), ..oakland.clone() };
+        assert_eq!(retained(&known), ["Row 132, Newark"]);
+    }
+}
@@ -1,96 +1,413 @@
+use chrono::{Datelike, Utc};
 use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::catalog::{self, Catalog, CatalogMake, CatalogModel};
+use crate::data_file::DataFile;
+use crate::models::SearchRadius;
+use crate::normalize;
+
+/// Model year bounds of a search; either end may be left open.
+pub type YearRange = (Option<u32>, Option<u32>);
+
+/// Fills in an open lower year bound.
+pub const OLDEST_MODEL_YEAR: u32 = 1900;
+
+/// Fills in an open upper year bound: next year's models are already on sale.
+pub fn newest_model_year() -> u32 {
+    Utc::now().year() as u32 + 1
+}
+
+/// How long to wait before asking the site about the same unknown make again.
+const SITE_LOOKUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// `distance` value sent for the search form's Unlimited option.
+const UNLIMITED_DISTANCE: &str = "0";
+
+fn distance_param(distance: SearchRadius) -> String {
+    match distance.miles() {
+        Some(miles) => miles.to_string(),
+        None => UNLIMITED_DISTANCE.to_string(),
+    }
+}
+
+/// Percent-encodes a query string value the way the search form submits it, so a
+/// postal code like "T2B 1N2" becomes "T2B%201N2".
+fn encode_query_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
 
 pub struct PicknPullSearch {
     base_url: String,
-    make_map: HashMap<String, u32>,
-    model_map: HashMap<(String, String), u32>, // (make, model) -> model_id
+    catalog_path: Option<PathBuf>, // Reloaded from here; unset for the bundled catalog
+    ids: RwLock<CatalogIds>,
+    site_lookups: Mutex<HashMap<String, Instant>>, // When each make was last looked up on the site
+}
+
+/// Lookup tables built from a [`Catalog`], keyed by folded name (see [`normalize::fold`]).
+#[derive(Default)]
+struct CatalogIds {
+    makes: HashMap<String, MakeIds>,
+}
+
+struct MakeIds {
+    key: String,
+    name: String,
+    id: u32,
+    models: HashMap<String, CatalogModel>,
+}
+
+impl CatalogIds {
+    fn from_catalog(catalog: &Catalog) -> Self {
+        let mut ids = Self::default();
+        for make in &catalog.makes {
+            let key = normalize::fold(&make.name);
+            let models = make
+                .models
+                .iter()
+                .map(|model| (normalize::fold(&model.name), model.clone()))
+                .collect();
+            ids.makes.insert(
+                key.clone(),
+                MakeIds { key, name: make.name.trim().to_string(), id: make.id, models },
+            );
+        }
+        ids
+    }
+}
+
+/// A make or model name that is not in the catalog, with near matches.
+#[derive(Debug, Clone)]
+pub enum LookupError {
+    UnsupportedMake { make: String, suggestions: Vec<String> },
+    UnsupportedModel { make: String, model: String, suggestions: Vec<String> },
+    /// A make-only search for a make the catalog has no models for
+    NoModels { make: String },
 }
 
+impl LookupError {
+    pub fn suggestions(&self) -> &[String] {
+        match self {
+            LookupError::UnsupportedMake { suggestions, .. }
+            | LookupError::UnsupportedModel { suggestions, .. } => suggestions,
+            LookupError::NoModels { .. } => &[],
+        }
+    }
+}
+
+impl std::fmt::Display for LookupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LookupError::UnsupportedMake { make, .. } => write!(f, "Unsupported make: {}", make)?,
+            LookupError::UnsupportedModel { make, model, .. } => {
+                write!(f, "Unsupported model: {} {}", make, model)?
+            }
+            LookupError::NoModels { make } => write!(f, "No models known for make: {}", make)?,
+        }
+        if !self.suggestions().is_empty() {
+            write!(f, " (did you mean {}?)", self.suggestions().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for LookupError {}
+
 impl PicknPullSearch {
+    /// Uses the catalog bundled with the binary.
     pub fn new() -> Self {
-        let mut make_map = HashMap::new();
-        let mut model_map = HashMap::new();
-        
-        // Common makes (you can expand this as needed)
-        make_map.insert("subaru".to_lowercase(), 226);
-        make_map.insert("honda".to_lowercase(), 120);
-        make_map.insert("toyota".to_lowercase(), 251);
-        make_map.insert("nissan".to_lowercase(), 185);
-        make_map.insert("ford".to_lowercase(), 95);
-        make_map.insert("chevrolet".to_lowercase(), 58);
-        make_map.insert("bmw".to_lowercase(), 34);
-        make_map.insert("mercedes".to_lowercase(), 159);
-        make_map.insert("audi".to_lowercase(), 20);
-        make_map.insert("volkswagen".to_lowercase(), 273);
-        
-        // Common Subaru models
-        model_map.insert(("subaru".to_string(), "impreza".to_string()), 4153);
-        model_map.insert(("subaru".to_string(), "impreza wagon".to_string()), 4154);
-        model_map.insert(("subaru".to_string(), "outback".to_string()), 4164);
-        model_map.insert(("subaru".to_string(), "forester".to_string()), 4157);
-        model_map.insert(("subaru".to_string(), "legacy".to_string()), 4160);
-        model_map.insert(("subaru".to_string(), "wrx".to_string()), 4170);
-        
-        // Common Honda models
-        model_map.insert(("honda".to_string(), "civic".to_string()), 2969);
-        model_map.insert(("honda".to_string(), "accord".to_string()), 2960);
-        model_map.insert(("honda".to_string(), "cr-v".to_string()), 2967);
-        model_map.insert(("honda".to_string(), "pilot".to_string()), 2985);
-        
-        // Common Toyota models
-        model_map.insert(("toyota".to_string(), "camry".to_string()), 6178);
-        model_map.insert(("toyota".to_string(), "corolla".to_string()), 6182);
-        model_map.insert(("toyota".to_string(), "prius".to_string()), 6209);
-        model_map.insert(("toyota".to_string(), "rav4".to_string()), 6212);
-        model_map.insert(("toyota".to_string(), "4runner".to_string()), 6165);
-        
+        Self::from_catalog(&Catalog::bundled())
+    }
+
+    pub fn from_catalog(catalog: &Catalog) -> Self {
         Self {
             base_url: "https://www.picknpull.com/check-inventory/vehicle-search".to_string(),
-            make_map,
-            model_map,
+            catalog_path: None,
+            ids: RwLock::new(CatalogIds::from_catalog(catalog)),
+            site_lookups: Mutex::default(),
+        }
+    }
+
+    /// Loads the catalog from a JSON file, which [`reload_catalog`](Self::reload_catalog) re-reads.
+    pub fn from_catalog_file(path: impl AsRef<Path>) -> Result<Self, catalog::Error> {
+        let catalog = Catalog::load(&path)?;
+        Ok(Self {
+            catalog_path: Some(path.as_ref().to_path_buf()),
+            ..Self::from_catalog(&catalog)
+        })
+    }
+
+    /// Builds URLs against another host (e.g. a local stand-in).
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn catalog_path(&self) -> Option<&Path> {
+        self.catalog_path.as_deref()
+    }
+
+    /// Re-reads the catalog file and swaps it in. The bundled catalog can't change,
+    /// so without a file this is [`catalog::Error::NotConfigured`].
+    pub fn reload_catalog(&self) -> Result<Catalog, catalog::Error> {
+        let path = self.catalog_path.as_ref().ok_or(catalog::Error::NotConfigured)?;
+        let catalog = Catalog::load(path)?;
+        self.replace_catalog(&catalog);
+        Ok(catalog)
+    }
+
+    pub fn replace_catalog(&self, catalog: &Catalog) {
+        *self.ids.write().unwrap() = CatalogIds::from_catalog(catalog);
+    }
+
+    /// The catalog in use, makes and models sorted by name.
+    pub fn catalog(&self) -> Catalog {
+        let ids = self.ids.read().unwrap();
+        let mut makes: Vec<CatalogMake> = ids
+            .makes
+            .values()
+            .map(|make| {
+                let mut models: Vec<CatalogModel> = make.models.values().cloned().collect();
+                models.sort_by(|a, b| a.name.cmp(&b.name));
+                CatalogMake { name: make.name.clone(), id: make.id, models }
+            })
+            .collect();
+        makes.sort_by(|a, b| a.name.cmp(&b.name));
+        Catalog { makes }
+    }
+
+    /// Adds a make read from the site, or replaces the catalog's entry for it.
+    /// An empty model list keeps the models the catalog already had.
+    pub fn add_make(&self, make: &CatalogMake) {
+        let mut catalog = self.catalog();
+        let key = normalize::fold(&make.name);
+        match catalog.makes.iter_mut().find(|known| normalize::fold(&known.name) == key) {
+            Some(known) if make.models.is_empty() => known.id = make.id,
+            Some(known) => *known = make.clone(),
+            None => catalog.makes.push(make.clone()),
+        }
+        self.replace_catalog(&catalog);
+    }
+
+    /// Whether `make` may be looked up on the site now: at most once an hour per
+    /// make, so repeated searches for a make the site doesn't have stay local.
+    pub fn claim_site_lookup(&self, make: &str) -> bool {
+        let mut lookups = self.site_lookups.lock().unwrap();
+        let now = Instant::now();
+        match lookups.get(&normalize::fold(make)) {
+            Some(last) if now.duration_since(*last) < SITE_LOOKUP_INTERVAL => false,
+            _ => {
+                lookups.insert(normalize::fold(make), now);
+                true
+            }
+        }
+    }
+
+    /// Resolves a make and model to their ids, accepting aliases and loose spelling
+    /// ("VW", "Mercedes-Benz", "CRV", "Impreza Wagon ").
+    pub fn resolve(&self, make: &str, model: &str) -> Result<(u32, u32), LookupError> {
+        let ids = self.ids.read().unwrap();
+
+        let make_ids = normalize::resolve_make(&ids.makes, make).ok_or_else(|| {
+            LookupError::UnsupportedMake {
+                make: make.to_string(),
+                suggestions: normalize::suggest(make, ids.makes.values().map(|m| m.name.as_str())),
+            }
+        })?;
+
+        let model_ids = normalize::resolve_model(&make_ids.models, &make_ids.key, model).ok_or_else(|| {
+            LookupError::UnsupportedModel {
+                make: make.to_string(),
+                model: model.to_string(),
+                suggestions: normalize::suggest(model, make_ids.models.values().map(|m| m.name.as_str())),
+            }
+        })?;
+
+        Ok((make_ids.id, model_ids.id))
+    }
+
+    /// Resolves several models of one make; no models means every model of the make.
+    pub fn resolve_models(&self, make: &str, models: &[String]) -> Result<(u32, Vec<u32>), LookupError> {
+        if models.is_empty() {
+            let ids = self.ids.read().unwrap();
+            let make_ids = normalize::resolve_make(&ids.makes, make).ok_or_else(|| {
+                LookupError::UnsupportedMake {
+                    make: make.to_string(),
+                    suggestions: normalize::suggest(make, ids.makes.values().map(|m| m.name.as_str())),
+                }
+            })?;
+            let mut model_ids: Vec<u32> = make_ids.models.values().map(|model| model.id).collect();
+            if model_ids.is_empty() {
+                return Err(LookupError::NoModels { make: make.to_string() });
+            }
+            model_ids.sort_unstable();
+            return Ok((make_ids.id, model_ids));
+        }
+
+        let mut make_id = 0;
+        let mut model_ids = Vec::new();
+        for model in models {
+            let (make, model) = self.resolve(make, model)?;
+            make_id = make;
+            if !model_ids.contains(&model) {
+                model_ids.push(model);
+            }
         }
+        Ok((make_id, model_ids))
     }
 
-    pub fn generate_search_url(&self, make: &str, model: &str, zip: &str, distance: u32, years: (u32, u32))
-        -> Result<String, String> {
-        let make_lower = make.to_lowercase();
-        let model_lower = model.to_lowercase();
-        
-        let make_id = self.make_map.get(&make_lower)
-            .ok_or_else(|| format!("Unsupported make: {}", make))?;
-        
-        let model_id = self.model_map.get(&(make_lower.clone(), model_lower))
-            .ok_or_else(|| format!("Unsupported model: {} {}", make, model))?;
-        
-        Ok(format!(
-            "{}?make={}&model={}&distance={}&zip={}&year={}-{}",
-            self.base_url, make_id, model_id, distance, zip, years.0, years.1
-        ))
-    }
-
-    pub fn generate_url(&self, make_id: u32, model_id: u32, zip: &str, distance: u32, years: (u32, u32))
+    pub fn generate_search_url(&self, make: &str, model: &str, zip: &str, distance: SearchRadius, years: YearRange)
+        -> Result<String, LookupError> {
+        let (make_id, model_id) = self.resolve(make, model)?;
+        Ok(self.generate_url(make_id, model_id, zip, distance, years))
+    }
+
+    /// One search URL per model, as the site only searches a single model at a time.
+    pub fn generate_search_urls(&self, make: &str, models: &[String], zip: &str, distance: SearchRadius, years: YearRange)
+        -> Result<Vec<String>, LookupError> {
+        let (make_id, model_ids) = self.resolve_models(make, models)?;
+        Ok(model_ids
+            .into_iter()
+            .map(|model_id| self.generate_url(make_id, model_id, zip, distance, years))
+            .collect())
+    }
+
+    /// Leaves out the year filter when neither bound is set, as the site recommends.
+    /// An open end is filled in with the oldest or newest model year.
+    pub fn generate_url(&self, make_id: u32, model_id: u32, zip: &str, distance: SearchRadius, years: YearRange)
         -> String {
-        format!(
-            "{}?make={}&model={}&distance={}&zip={}&year={}-{}",
-            self.base_url, make_id, model_id, distance, zip, years.0, years.1
-        )
+        let mut url = format!(
+            "{}?make={}&model={}&distance={}&zip={}",
+            self.base_url, make_id, model_id, distance_param(distance), encode_query_value(zip)
+        );
+        if years != (None, None) {
+            let year_min = years.0.unwrap_or(OLDEST_MODEL_YEAR);
+            let year_max = years.1.unwrap_or_else(newest_model_year);
+            url.push_str(&format!("&year={}-{}", year_min, year_max));
+        }
+        url
     }
 
     // Subaru Impreza Wagon specific search TODO just for testing
-    pub fn subaru_impreza_wagon_search(&self, zip: &str, distance: u32) -> String {
+    pub fn subaru_impreza_wagon_search(&self, zip: &str, distance: SearchRadius) -> String {
         // Make ID 226 = Subaru, Model ID 4154 = Impreza Wagon
-        self.generate_url(226, 4154, zip, distance, (2000, 2006))
+        self.generate_url(226, 4154, zip, distance, (Some(2000), Some(2006)))
     }
     
     pub fn get_supported_makes(&self) -> Vec<String> {
-        self.make_map.keys().cloned().collect()
+        self.ids.read().unwrap().makes.values()
+            .map(|make| make.name.to_lowercase())
+            .collect()
     }
     
     pub fn get_supported_models_for_make(&self, make: &str) -> Vec<String> {
-        let make_lower = make.to_lowercase();
-        self.model_map.keys()
-            .filter(|(m, _)| m == &make_lower)
-            .map(|(_, model)| model.clone())
-            .collect()
+        let ids = self.ids.read().unwrap();
+        normalize::resolve_make(&ids.makes, make)
+            .map(|make| make.models.values().map(|model| model.name.to_lowercase()).collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Default for PicknPullSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::CatalogMake;
+
+    #[test]
+    fn reloads_catalog_file() {
+        let path = std::env::temp_dir().join(format!("pnp_catalog_{}.json", std::process::id()));
+        let mut catalog = Catalog::bundled();
+        catalog.save(&path).unwrap();
+
+        let search = PicknPullSearch::from_catalog_file(&path).unwrap();
+        assert_eq!(search.resolve("Subaru", "Impreza Wagon").unwrap(), (226, 4154));
+        assert!(search.resolve("Mazda", "Miata").is_err());
+
+        catalog.makes.push(CatalogMake {
+            name: "Mazda".to_string(),
+            id: 1,
+            models: vec![CatalogModel { name: "Miata".to_string(), id: 2 }],
+        });
+        catalog.save(&path).unwrap();
+        search.reload_catalog().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(search.resolve("Mazda", "Miata").unwrap(), (1, 2));
+        assert!(matches!(PicknPullSearch::new().reload_catalog(), Err(catalog::Error::NotConfigured)));
+    }
+
+    #[test]
+    fn resolves_loose_names_and_suggests_typos() {
+        let search = PicknPullSearch::new();
+        assert_eq!(search.resolve("subaru", "Impreza Wagon ").unwrap(), (226, 4154));
+        assert_eq!(search.resolve("Honda", "CRV").unwrap(), (120, 2967));
+
+        let error = search.resolve("Subru", "Impreza").unwrap_err();
+        assert_eq!(error.suggestions(), ["Subaru"]);
+        assert_eq!(error.to_string(), "Unsupported make: Subru (did you mean Subaru?)");
+    }
+
+    #[test]
+    fn generates_one_url_per_model() {
+        let search = PicknPullSearch::new();
+        let (_, subaru_models) = search.resolve_models("subaru", &[]).unwrap();
+        assert!(subaru_models.contains(&4154), "{:?}", subaru_models);
+
+        // The same model twice is searched once
+        let models = ["Impreza".to_string(), "impreza".to_string(), "Outback".to_string()];
+        let urls = search
+            .generate_search_urls("subaru", &models, "94560", SearchRadius::Miles50, (Some(2000), Some(2006)))
+            .unwrap();
+        assert_eq!(urls.len(), 2);
+        assert!(urls.iter().all(|url| url.contains("make=226&model=") && url.ends_with("&year=2000-2006")));
+    }
+
+    #[test]
+    fn sends_each_radius_as_the_form_does() {
+        let search = PicknPullSearch::new();
+        let url = |distance| search.generate_url(226, 4154, "94560", distance, (None, None));
+        assert!(url(SearchRadius::Miles10).contains("&distance=10&"));
+        assert!(url(SearchRadius::Miles500).contains("&distance=500&"));
+        // The form's Unlimited option has no mileage of its own
+        assert!(url(SearchRadius::Unlimited).contains("&distance=0&"));
+    }
+
+    #[test]
+    fn encodes_postal_codes_with_spaces() {
+        let search = PicknPullSearch::new();
+        let url = search.generate_url(226, 4154, "T2B 1N2", SearchRadius::Miles50, (None, None));
+        assert!(url.ends_with("&zip=T2B%201N2"), "{}", url);
+        let url = search.generate_url(226, 4154, "94560-1234", SearchRadius::Miles50, (None, None));
+        assert!(url.ends_with("&zip=94560-1234"), "{}", url);
+        assert_eq!(encode_query_value("a&b=c"), "a%26b%3Dc");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fills_in_open_year_bounds() {
+        let search = PicknPullSearch::new();
+        let url = |years| search.generate_url(226, 4154, "94560", SearchRadius::Miles50, years);
+        assert!(url((Some(1990), None)).ends_with(&format!("&year=1990-{}", newest_model_year())));
+        assert!(url((None, Some(2006))).ends_with("&year=1900-2006"));
+        assert!(!url((None, None)).contains("year="));
+    }
+}
@@ -0,0 +1,137 @@
+use axum::Json;
+use chrono::Utc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+use crate::api::{run_search, AppState};
+use crate::inventory::vehicle_key;
+use crate::models::{ArrivalEvent, JunkyardItem, SavedSearch};
+use crate::notifier;
+use crate::watchlist::WatchStore;
+
+/// Result of running one saved search and diffing it against its earlier runs.
+pub struct WatchCheck {
+    pub vehicles: Vec<JunkyardItem>,
+    pub arrivals: Vec<ArrivalEvent>,
+}
+
+/// Runs a saved search and records a "new vehicle" event for every vehicle no
+/// earlier run listed, passing the events on to the notifiers.
+///
+/// A failed run leaves the watch untouched, so its next successful run is still
+/// compared against the last good one.
+pub async fn check_watch(
+    state: &AppState,
+    watches: &WatchStore,
+    watch: &SavedSearch,
+) -> Result<WatchCheck, String> {
+    let vehicles = run_search(state, &watch.search)
+        .await
+        .map_err(|(_, Json(error))| error.error)?
+        .vehicles;
+
+    let keyed: Vec<(String, JunkyardItem)> = vehicles
+        .iter()
+        .map(|vehicle| (vehicle_key(vehicle), vehicle.clone()))
+        .collect();
+    let arrivals = watches
+        .record_run(watch, &keyed, Utc::now())
+        .map_err(|e| e.to_string())?;
+    notifier::dispatch(&state.notifiers, &arrivals);
+
+    Ok(WatchCheck { vehicles, arrivals })
+}
+
+/// Runs every saved search once, returning the new arrivals found.
+pub async fn run_all_watches(state: &AppState) -> Vec<ArrivalEvent> {
+    let Some(watches) = state.watches.clone() else {
+        return Vec::new();
+    };
+
+    let list = match watches.list(None) {
+        Ok(list) => list,
+        Err(e) => {
+            println!("Scheduler could not load saved searches: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let mut arrivals = Vec::new();
+    for watch in list {
+        match check_watch(state, &watches, &watch).await {
+            Ok(check) => {
+                if !check.arrivals.is_empty() {
+                    println!(
+                        "🚨 {} new vehicle(s) for saved search '{}'",
+                        check.arrivals.len(),
+                        watch.name
+                    );
+                }
+                arrivals.extend(check.arrivals);
+            }
+            Err(e) => println!("Saved search '{}' (id {}) failed: {}", watch.name, watch.id, e),
+        }
+    }
+
+    arrivals
+}
+
+/// Starts a background task that re-runs all saved searches every `interval`.
+pub fn spawn(state: AppState, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            run_all_watches(&state).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SearchRadius, SearchRequest};
+    use crate::pick_n_pull::PicknPullSearch;
+    use crate::test_support::StubFetcher;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn reports_only_vehicles_new_since_last_run() {
+        let sample = include_str!("../firecrawl_sample_output.md");
+        let fetcher = Arc::new(StubFetcher::serving(sample));
+        let state = AppState::new(fetcher.clone(), PicknPullSearch::new())
+            .with_watches(WatchStore::open_in_memory().unwrap());
+        let watches = state.watches.clone().unwrap();
+
+        let search = SearchRequest {
+            make: "subaru".to_string(),
+            models: vec!["impreza wagon".to_string()],
+            year_min: Some(2000),
+            year_max: Some(2006),
+            zip_code: "95014".to_string(),
+            distance: Some(SearchRadius::Miles50),
+            parser: Some(crate::models::ParserMode::Markdown),
+            store_id: None,
+            sort: None,
+            max_miles: None,
+        };
+        watches.create("wagons", "sam", &search).unwrap();
+
+        // First run only sets the baseline
+        assert!(run_all_watches(&state).await.is_empty());
+
+        let second_car = "| ![2004 Subaru Impreza Wagon](https://cdn.row52.com/images/x.JPG) | 2004 | Subaru | Impreza Wagon | 140 | 04/09/2025 | |\n\nDisplaying 2 vehicles";
+        fetcher.serve(&sample.replace("Displaying 1 vehicles", second_car));
+
+        let arrivals = run_all_watches(&state).await;
+        assert_eq!(arrivals.len(), 1);
+        assert_eq!(arrivals[0].vehicle_key, "47:2004_subaru_impreza_wagon_140");
+        assert_eq!(watches.list_events(None, None).unwrap().len(), 1);
+
+        // Nothing new the third time round
+        assert!(run_all_watches(&state).await.is_empty());
+    }
+}
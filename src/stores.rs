@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::inventory::Error;
+use crate::models::{Store, StoreLocation};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS stores (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    state TEXT NOT NULL,
+    zip TEXT NOT NULL,
+    store_id INTEGER,
+    location_url TEXT,
+    updated_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_stores_zip ON stores(zip);
+";
+
+/// SQLite-backed directory of every Pick-n-Pull yard, kept up to date from the
+/// "Locations" list on crawled pages.
+pub struct StoreDirectory {
+    conn: Mutex<Connection>,
+}
+
+impl StoreDirectory {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Adds or refreshes locations. Store ids and location pages learnt from search
+    /// results are kept.
+    pub fn upsert_locations(&self, locations: &[StoreLocation], seen_at: DateTime<Utc>) -> Result<(), Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for location in locations {
+            tx.execute(
+                "INSERT INTO stores (id, name, state, zip, store_id, location_url, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(id) DO UPDATE SET
+                     name = excluded.name,
+                     state = excluded.state,
+                     zip = excluded.zip,
+                     store_id = COALESCE(excluded.store_id, stores.store_id),
+                     location_url = COALESCE(excluded.location_url, stores.location_url),
+                     updated_at = excluded.updated_at",
+                params![
+                    location.id,
+                    location.name,
+                    location.state,
+                    location.zip,
+                    location.store_id,
+                    location.location_url,
+                    seen_at,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Fills in the store id and location page of the listed yard with the same zip
+    /// as a store seen in search results. Ambiguous zips are left alone.
+    pub fn link_store(&self, store: &Store) -> Result<(), Error> {
+        let (Some(store_id), Some(zip)) = (store.store_id, store.zip.as_deref()) else {
+            return Ok(());
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE stores SET store_id = ?1, location_url = COALESCE(?2, location_url)
+             WHERE zip = ?3 AND (SELECT COUNT(*) FROM stores WHERE zip = ?3) = 1",
+            params![store_id, store.url, zip.trim().to_uppercase()],
+        )?;
+        Ok(())
+    }
+
+    /// All yards, or those in one state/province, ordered by state and name.
+    pub fn list(&self, state: Option<&str>) -> Result<Vec<StoreLocation>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, state, zip, store_id, location_url FROM stores
+             WHERE ?1 IS NULL OR state = ?1 COLLATE NOCASE
+             ORDER BY state, name",
+        )?;
+        let stores = stmt
+            .query_map(params![state], location_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(stores)
+    }
+
+    /// Looks a yard up by its slug ("newark") or its numeric Pick-n-Pull store id.
+    pub fn get(&self, id: &str) -> Result<Option<StoreLocation>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let store = conn
+            .query_row(
+                "SELECT id, name, state, zip, store_id, location_url FROM stores
+                 WHERE id = ?1 OR CAST(store_id AS TEXT) = ?1
+                 ORDER BY id = ?1 DESC LIMIT 1",
+                params![id.trim().to_lowercase()],
+                location_from_row,
+            )
+            .optional()?;
+        Ok(store)
+    }
+}
+
+fn location_from_row(row: &rusqlite::Row) -> rusqlite::Result<StoreLocation> {
+    Ok(StoreLocation {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        state: row.get(2)?,
+        zip: row.get(3)?,
+        store_id: row.get(4)?,
+        location_url: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_junkyard_page, parse_locations};
+
+    #[test]
+    fn builds_directory_and_links_store_ids() {
+        let page = include_str!("../firecrawl_sample_output.md");
+        let directory = StoreDirectory::open_in_memory().unwrap();
+        directory.upsert_locations(&parse_locations(Some(page), None), Utc::now()).unwrap();
+
+        assert_eq!(directory.list(Some("alberta")).unwrap().len(), 3);
+        assert!(directory.get("newark").unwrap().unwrap().store_id.is_none());
+
+        let items = parse_junkyard_page(page, "");
+        directory.link_store(items[0].store.as_ref().unwrap()).unwrap();
+        // A later crawl of the list must not forget the id
+        directory.upsert_locations(&parse_locations(Some(page), None), Utc::now()).unwrap();
+
+        let newark = directory.get("47").unwrap().unwrap();
+        assert_eq!(newark.id, "newark");
+        assert_eq!(newark.location_url.as_deref(), Some("https://www.picknpull.com/locations/47/newark-ca"));
+    }
+}
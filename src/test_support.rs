@@ -0,0 +1,78 @@
+//! Fakes shared by the unit tests.
+
+use async_trait::async_trait;
+use axum::Router;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use tokio::net::TcpListener;
+
+use crate::fetcher::PageFetcher;
+use crate::firecrawl_client::{CrawlData, CrawlResponse, Error, ScrapeFormat};
+
+/// Serves canned markdown by URL, or the same page for every URL; other URLs
+/// get a response with no page. Pages added with `with_html_page` are served as HTML.
+#[derive(Default)]
+pub struct StubFetcher {
+    pages: HashMap<String, String>,
+    html_pages: HashMap<String, String>,
+    every_url: Mutex<Option<String>>,
+}
+
+impl StubFetcher {
+    pub fn with_pages(pages: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self {
+            pages: pages.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_html_page(mut self, url: impl Into<String>, html: impl Into<String>) -> Self {
+        self.html_pages.insert(url.into(), html.into());
+        self
+    }
+
+    pub fn serving(markdown: &str) -> Self {
+        let fetcher = Self::default();
+        fetcher.serve(markdown);
+        fetcher
+    }
+
+    /// Serves `markdown` for every URL from now on.
+    pub fn serve(&self, markdown: &str) {
+        *self.every_url.lock().unwrap() = Some(markdown.to_string());
+    }
+}
+
+#[async_trait]
+impl PageFetcher for StubFetcher {
+    async fn fetch_page(&self, url: &str, _formats: &[ScrapeFormat]) -> Result<CrawlResponse, Error> {
+        if let Some(html) = self.html_pages.get(url) {
+            return Ok(CrawlResponse {
+                success: true,
+                data: Some(CrawlData {
+                    markdown: None,
+                    html: Some(html.clone()),
+                    url: Some(url.to_string()),
+                }),
+            });
+        }
+        let markdown = self.pages.get(url).cloned().or_else(|| self.every_url.lock().unwrap().clone());
+        Ok(CrawlResponse {
+            success: true,
+            data: markdown.map(|markdown| CrawlData {
+                markdown: Some(markdown),
+                html: None,
+                url: Some(url.to_string()),
+            }),
+        })
+    }
+}
+
+/// Serves `app` on a free local port for the rest of the test.
+pub async fn spawn_server(app: Router) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
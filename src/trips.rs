@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::geo::{distance_miles, Coordinates, ZipCentroids};
+use crate::models::{JunkyardItem, Store, TripMatch, TripPlan, TripStop};
+use crate::normalize;
+
+/// A yard with at least one wanted item.
+struct Candidate {
+    store: Store,
+    location: Coordinates,
+    matches: Vec<TripMatch>,
+}
+
+/// Picks the yards to visit from `home` and the order to visit them in.
+///
+/// `wanted` pairs each wanted item's label with the vehicles found for it. Yards
+/// are added greedily, the one covering the most still-uncovered items first (the
+/// shorter detour on a tie), each inserted where it lengthens the round trip
+/// least, until no other yard fits within `max_total_miles`. Yards whose zip
+/// can't be located are left out.
+pub fn plan_route(
+    home: Coordinates,
+    wanted: &[(String, Vec<JunkyardItem>)],
+    centroids: &ZipCentroids,
+    max_total_miles: Option<f64>,
+) -> TripPlan {
+    let candidates = group_by_store(wanted, centroids);
+
+    let mut route: Vec<usize> = Vec::new();
+    let mut covered: HashSet<usize> = HashSet::new();
+    let mut length = 0.0;
+    loop {
+        let mut best: Option<(usize, f64, usize, usize)> = None;
+        for (index, candidate) in candidates.iter().enumerate() {
+            if route.contains(&index) {
+                continue;
+            }
+            let new_items = candidate
+                .matches
+                .iter()
+                .filter(|m| !covered.contains(&m.wanted))
+                .count();
+            if new_items == 0 {
+                continue;
+            }
+            let (position, added) = cheapest_insertion(home, &route, &candidates, candidate.location);
+            if max_total_miles.is_some_and(|max| length + added > max) {
+                continue;
+            }
+            let better = best.is_none_or(|(best_items, best_added, _, _)| {
+                new_items > best_items || (new_items == best_items && added < best_added)
+            });
+            if better {
+                best = Some((new_items, added, index, position));
+            }
+        }
+
+        let Some((_, added, index, position)) = best else {
+            break;
+        };
+        route.insert(position, index);
+        length += added;
+        covered.extend(candidates[index].matches.iter().map(|m| m.wanted));
+    }
+
+    let mut stops = Vec::new();
+    let mut total_miles = 0.0;
+    let mut previous = home;
+    let mut candidates: Vec<Option<Candidate>> = candidates.into_iter().map(Some).collect();
+    for index in route {
+        let candidate = candidates[index].take().expect("each yard is visited once");
+        let leg_miles = distance_miles(previous, candidate.location);
+        total_miles += leg_miles;
+        previous = candidate.location;
+        stops.push(TripStop {
+            store: candidate.store,
+            leg_miles: round_miles(leg_miles),
+            matches: candidate.matches,
+        });
+    }
+    total_miles += distance_miles(previous, home);
+
+    let (covered, not_covered) = wanted
+        .iter()
+        .enumerate()
+        .partition::<Vec<_>, _>(|(index, _)| covered.contains(index));
+    let labels = |items: Vec<(usize, &(String, Vec<JunkyardItem>))>| {
+        items.into_iter().map(|(_, (label, _))| label.clone()).collect()
+    };
+    TripPlan {
+        stops,
+        total_miles: round_miles(total_miles),
+        covered: labels(covered),
+        not_covered: labels(not_covered),
+    }
+}
+
+/// Where in `route` a stop at `location` adds the fewest miles, and how many.
+fn cheapest_insertion(
+    home: Coordinates,
+    route: &[usize],
+    candidates: &[Candidate],
+    location: Coordinates,
+) -> (usize, f64) {
+    let points: Vec<Coordinates> = std::iter::once(home)
+        .chain(route.iter().map(|&index| candidates[index].location))
+        .chain(std::iter::once(home))
+        .collect();
+    points
+        .windows(2)
+        .map(|leg| {
+            distance_miles(leg[0], location) + distance_miles(location, leg[1]) - distance_miles(leg[0], leg[1])
+        })
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("a route always has a leg")
+}
+
+fn group_by_store(wanted: &[(String, Vec<JunkyardItem>)], centroids: &ZipCentroids) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+    for (wanted_index, (label, vehicles)) in wanted.iter().enumerate() {
+        for item in vehicles {
+            let Some(store) = &item.store else {
+                continue;
+            };
+            let Some(location) = store.zip.as_deref().and_then(|zip| centroids.locate(zip)) else {
+                continue;
+            };
+            let key = match store.store_id {
+                Some(id) => id.to_string(),
+                None => normalize::fold(&store.name),
+            };
+            let index = *by_key.entry(key).or_insert_with(|| {
+                candidates.push(Candidate {
+                    store: store.clone(),
+                    location,
+                    matches: Vec::new(),
+                });
+                candidates.len() - 1
+            });
+
+            let matches = &mut candidates[index].matches;
+            match matches.iter_mut().find(|m| m.wanted == wanted_index) {
+                Some(found) => found.vehicles.push(item.clone()),
+                None => matches.push(TripMatch {
+                    wanted: wanted_index,
+                    label: label.clone(),
+                    vehicles: vec![item.clone()],
+                }),
+            }
+        }
+    }
+    candidates
+}
+
+fn round_miles(miles: f64) -> f64 {
+    (miles * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_file::DataFile;
+    use crate::parser::parse_junkyard_page;
+
+    fn at_yard(item: &JunkyardItem, store_id: u32, name: &str, zip: &str) -> JunkyardItem {
+        let mut item = item.clone();
+        let store = item.store.as_mut().unwrap();
+        store.store_id = Some(store_id);
+        store.name = name.to_string();
+        store.zip = Some(zip.to_string());
+        item
+    }
+
+    #[test]
+    fn covers_most_items_within_the_distance() {
+        let centroids = ZipCentroids::bundled();
+        let home = centroids.locate("94560").unwrap();
+        let items = parse_junkyard_page(include_str!("../firecrawl_sample_output.md"), "");
+        let newark = |item: &JunkyardItem| at_yard(item, 47, "Newark", "94560");
+        let oakland = |item: &JunkyardItem| at_yard(item, 52, "Oakland", "94621");
+        let fresno = |item: &JunkyardItem| at_yard(item, 60, "Fresno", "93706");
+
+        let wanted = vec![
+            ("engine".to_string(), vec![newark(&items[0]), oakland(&items[0])]),
+            ("headlight".to_string(), vec![oakland(&items[0])]),
+            ("seats".to_string(), vec![fresno(&items[0])]),
+        ];
+
+        // Oakland alone covers two items; Fresno is too far for the limit
+        let plan = plan_route(home, &wanted, &centroids, Some(60.0));
+        let stops: Vec<_> = plan.stops.iter().map(|stop| stop.store.name.as_str()).collect();
+        assert_eq!(stops, ["Oakland"]);
+        assert_eq!(plan.covered, ["engine", "headlight"]);
+        assert_eq!(plan.not_covered, ["seats"]);
+        assert!(plan.total_miles > 25.0 && plan.total_miles < 40.0, "{}", plan.total_miles);
+
+        let plan = plan_route(home, &wanted, &centroids, None);
+        assert_eq!(plan.stops.len(), 2);
+        assert!(plan.not_covered.is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+use crate::models::{FieldError, PartSearchRequest, SearchRequest, TripPlanRequest};
+use crate::pick_n_pull::{newest_model_year, OLDEST_MODEL_YEAR};
+
+/// Checks every field of a search and reports all problems at once.
+pub fn validate_search(request: &SearchRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if request.make.trim().is_empty() {
+        errors.push(FieldError::new("make", "required", "Make is required"));
+    }
+    if request.models.iter().any(|model| model.trim().is_empty()) {
+        errors.push(FieldError::new("model", "required", "Model names must not be empty"));
+    }
+    match &request.store_id {
+        // A store search uses the yard's own zip, so one isn't required
+        Some(store_id) if store_id.trim().is_empty() => {
+            errors.push(FieldError::new("store_id", "required", "Store id must not be empty"));
+        }
+        Some(_) if request.zip_code.trim().is_empty() => {}
+        _ => check_zip_code(&request.zip_code, &mut errors),
+    }
+    check_miles("max_miles", request.max_miles, &mut errors);
+
+    let newest = newest_model_year();
+    for (field, year) in [("year_min", request.year_min), ("year_max", request.year_max)] {
+        if let Some(year) = year.filter(|year| !(OLDEST_MODEL_YEAR..=newest).contains(year)) {
+            errors.push(FieldError::new(
+                field,
+                "out_of_range",
+                format!("{} is not a model year, expected {}-{}", year, OLDEST_MODEL_YEAR, newest),
+            ));
+        }
+    }
+    if let (Some(year_min), Some(year_max)) = (request.year_min, request.year_max) {
+        if year_min > year_max {
+            errors.push(FieldError::new(
+                "year_min",
+                "invalid_range",
+                "year_min cannot be greater than year_max",
+            ));
+        }
+    }
+
+    finish(errors)
+}
+
+pub fn validate_part_search(request: &PartSearchRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    if request.part.trim().is_empty() {
+        errors.push(FieldError::new("part", "required", "Part is required"));
+    }
+    check_zip_code(&request.zip_code, &mut errors);
+    check_miles("max_miles", request.max_miles, &mut errors);
+    finish(errors)
+}
+
+/// Checks the trip and each wanted item; problems with an item are reported as
+/// `wanted[<index>].<field>`.
+pub fn validate_trip(request: &TripPlanRequest) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    check_zip_code(&request.zip_code, &mut errors);
+    check_miles("max_total_miles", request.max_total_miles, &mut errors);
+    if request.wanted.is_empty() {
+        errors.push(FieldError::new("wanted", "required", "List at least one wanted vehicle or part"));
+    }
+
+    for (index, item) in request.wanted.iter().enumerate() {
+        let field = |name: &str| format!("wanted[{}].{}", index, name);
+        match (&item.make, &item.part) {
+            (Some(_), Some(_)) => errors.push(FieldError::new(
+                format!("wanted[{}]", index),
+                "conflicting_fields",
+                "Give either a make or a part, not both",
+            )),
+            (None, None) => errors.push(FieldError::new(
+                format!("wanted[{}]", index),
+                "required",
+                "Give a make or a part",
+            )),
+            (None, Some(part)) => {
+                if part.trim().is_empty() {
+                    errors.push(FieldError::new(field("part"), "required", "Part is required"));
+                }
+            }
+            (Some(_), None) => {
+                let search = item.search_request(&request.zip_code, None, None);
+                if let Err(item_errors) = validate_search(&search) {
+                    errors.extend(
+                        item_errors
+                            .into_iter()
+                            // The zip is the trip's, already checked above
+                            .filter(|error| error.field != "zip_code")
+                            .map(|error| FieldError { field: field(&error.field), ..error }),
+                    );
+                }
+            }
+        }
+    }
+
+    finish(errors)
+}
+
+fn finish(errors: Vec<FieldError>) -> Result<(), Vec<FieldError>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// Yards are in the US and in Alberta/BC, so both zip and postal codes are accepted
+fn check_zip_code(zip_code: &str, errors: &mut Vec<FieldError>) {
+    let zip_code = zip_code.trim();
+    if zip_code.is_empty() {
+        errors.push(FieldError::new("zip_code", "required", "Zip code is required"));
+    } else if !is_us_zip(zip_code) && !is_canadian_postal_code(zip_code) {
+        errors.push(FieldError::new(
+            "zip_code",
+            "invalid_format",
+            format!("'{}' is not a US zip code (12345) or Canadian postal code (T2P 1J9)", zip_code),
+        ));
+    }
+}
+
+fn check_miles(field: &str, miles: Option<f64>, errors: &mut Vec<FieldError>) {
+    if miles.is_some_and(|miles| !miles.is_finite() || miles <= 0.0) {
+        errors.push(FieldError::new(field, "out_of_range", format!("{} must be a positive number", field)));
+    }
+}
+
+/// "12345" or ZIP+4 "12345-6789".
+fn is_us_zip(zip: &str) -> bool {
+    let (zip, plus_four) = match zip.split_once('-') {
+        Some((zip, plus_four)) => (zip, Some(plus_four)),
+        None => (zip, None),
+    };
+    let digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    digits(zip, 5) && plus_four.is_none_or(|p| digits(p, 4))
+}
+
+/// "A1A 1A1", with or without the space. D, F, I, O, Q and U are never used,
+/// nor W and Z as the first letter.
+fn is_canadian_postal_code(code: &str) -> bool {
+    let chars: Vec<char> = code.chars().filter(|c| !c.is_whitespace()).collect();
+    let letter = |c: char| c.is_ascii_alphabetic() && !"DFIOQU".contains(c.to_ascii_uppercase());
+    chars.len() == 6
+        && chars.iter().enumerate().all(|(i, &c)| if i % 2 == 0 { letter(c) } else { c.is_ascii_digit() })
+        && !"WZ".contains(chars[0].to_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SearchRadius;
+
+    fn search(zip_code: &str, distance: Option<SearchRadius>, years: (Option<u32>, Option<u32>)) -> SearchRequest {
+        SearchRequest {
+            make: "Subaru".to_string(),
+            models: vec!["Impreza".to_string()],
+            year_min: years.0,
+            year_max: years.1,
+            zip_code: zip_code.to_string(),
+            distance,
+            parser: None,
+            store_id: None,
+            sort: None,
+            max_miles: None,
+        }
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        assert!(validate_search(&search("94560", Some(SearchRadius::Miles50), (Some(2000), None))).is_ok());
+        assert!(validate_search(&search("94560-1234", None, (None, None))).is_ok());
+        assert!(validate_search(&search("T2P 1J9", Some(SearchRadius::Unlimited), (None, None))).is_ok());
+        assert!(validate_search(&search("v6b1a1", None, (None, None))).is_ok());
+
+        let errors = validate_search(&search("9456", None, (Some(0), Some(3000)))).unwrap_err();
+        let fields: Vec<_> = errors.iter().map(|e| (e.field.as_str(), e.code.as_str())).collect();
+        assert_eq!(
+            fields,
+            [
+                ("zip_code", "invalid_format"),
+                ("year_min", "out_of_range"),
+                ("year_max", "out_of_range"),
+            ]
+        );
+
+        let errors = validate_search(&search("D2P 1J9", None, (Some(2007), Some(2002)))).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].code, "invalid_range");
+    }
+
+    #[test]
+    fn rounds_distances_up_to_an_offered_radius() {
+        let distance = |value: serde_json::Value| {
+            serde_json::from_value::<SearchRequest>(serde_json::json!({
+                "make": "Subaru", "zip_code": "94560", "distance": value
+            }))
+            .map(|request| request.distance)
+        };
+        assert_eq!(distance(serde_json::json!(30)).unwrap(), Some(SearchRadius::Miles50));
+        assert_eq!(distance(serde_json::json!(50.5)).unwrap(), Some(SearchRadius::Miles100));
+        assert_eq!(distance(serde_json::json!(500)).unwrap(), Some(SearchRadius::Miles500));
+        assert_eq!(distance(serde_json::json!("unlimited")).unwrap(), Some(SearchRadius::Unlimited));
+
+        // Past the largest radius, every yard has to be asked for by name
+        let error = distance(serde_json::json!(600)).unwrap_err().to_string();
+        assert!(error.starts_with("distance 600 is over the 500 mile limit"), "{}", error);
+        assert!(distance(serde_json::json!("501")).is_err());
+
+        // No radius holds nothing at all
+        assert!(distance(serde_json::json!(0)).is_err());
+        assert!(distance(serde_json::json!(-5)).is_err());
+        assert!(distance(serde_json::json!("far")).is_err());
+        assert!("0".parse::<SearchRadius>().is_err());
+    }
+
+    #[test]
+    fn trips_search_far_enough_to_reach_every_stop() {
+        let radius = |max_total_miles: Option<f64>, distance: Option<SearchRadius>| {
+            let request = TripPlanRequest {
+                zip_code: "94560".to_string(),
+                wanted: Vec::new(),
+                max_total_miles,
+                distance,
+                parser: None,
+            };
+            request.search_radius()
+        };
+        assert_eq!(radius(Some(120.0), None), Some(SearchRadius::Miles100));
+        assert_eq!(radius(Some(1000.0), None), Some(SearchRadius::Miles500));
+        assert_eq!(radius(Some(1001.0), None), Some(SearchRadius::Unlimited));
+        assert_eq!(radius(Some(5000.0), Some(SearchRadius::Miles25)), Some(SearchRadius::Miles25));
+        assert_eq!(radius(None, None), None);
+    }
+
+    #[test]
+    fn store_search_needs_no_zip_code() {
+        let store_search = |store_id: &str| SearchRequest {
+            store_id: Some(store_id.to_string()),
+            ..search("", None, (None, None))
+        };
+        assert!(validate_search(&store_search("newark")).is_ok());
+
+        let errors = validate_search(&store_search(" ")).unwrap_err();
+        assert_eq!((errors[0].field.as_str(), errors[0].code.as_str()), ("store_id", "required"));
+    }
+}
@@ -0,0 +1,340 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::inventory::Error;
+use crate::models::{ArrivalEvent, JunkyardItem, SavedSearch, SearchRequest};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS watches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    search_json TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    last_run_at TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_watches_owner ON watches(owner);
+
+CREATE TABLE IF NOT EXISTS watch_snapshots (
+    watch_id INTEGER NOT NULL,
+    vehicle_key TEXT NOT NULL,
+    PRIMARY KEY (watch_id, vehicle_key)
+);
+
+CREATE TABLE IF NOT EXISTS arrival_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    watch_id INTEGER NOT NULL,
+    watch_name TEXT NOT NULL,
+    vehicle_key TEXT NOT NULL,
+    vehicle_json TEXT NOT NULL,
+    detected_at TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_arrival_events_watch ON arrival_events(watch_id, detected_at);
+
+CREATE TABLE IF NOT EXISTS notifier_cursors (
+    notifier TEXT PRIMARY KEY,
+    last_event_id INTEGER NOT NULL
+);
+";
+
+/// SQLite-backed list of saved searches.
+pub struct WatchStore {
+    conn: Mutex<Connection>,
+}
+
+impl WatchStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, Error> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn create(&self, name: &str, owner: &str, search: &SearchRequest) -> Result<SavedSearch, Error> {
+        let conn = self.conn.lock().unwrap();
+        let created_at = Utc::now();
+        conn.execute(
+            "INSERT INTO watches (name, owner, search_json, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![name, owner, serde_json::to_string(search)?, created_at],
+        )?;
+
+        Ok(SavedSearch {
+            id: conn.last_insert_rowid(),
+            name: name.to_string(),
+            owner: owner.to_string(),
+            search: search.clone(),
+            created_at,
+            last_run_at: None,
+        })
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<SavedSearch>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT id, name, owner, search_json, created_at, last_run_at FROM watches WHERE id = ?1",
+                params![id],
+                watch_row,
+            )
+            .optional()?;
+        row.map(into_saved_search).transpose()
+    }
+
+    /// All saved searches, or only those belonging to `owner`.
+    pub fn list(&self, owner: Option<&str>) -> Result<Vec<SavedSearch>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, owner, search_json, created_at, last_run_at FROM watches
+             WHERE ?1 IS NULL OR owner = ?1
+             ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![owner], watch_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter().map(into_saved_search).collect()
+    }
+
+    /// Returns whether a watch with this id existed.
+    pub fn delete(&self, id: i64) -> Result<bool, Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let deleted = tx.execute("DELETE FROM watches WHERE id = ?1", params![id])?;
+        tx.execute("DELETE FROM watch_snapshots WHERE watch_id = ?1", params![id])?;
+        tx.execute("DELETE FROM arrival_events WHERE watch_id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Records an arrival event for every vehicle in a run's results that no
+    /// earlier run of the watch listed.
+    ///
+    /// The first run of a watch only sets the baseline and reports nothing. `vehicles`
+    /// must be the watch's full results: vehicles missing from them leave the
+    /// snapshot, so one that comes back later is reported again.
+    pub fn record_run(
+        &self,
+        watch: &SavedSearch,
+        vehicles: &[(String, JunkyardItem)],
+        run_at: DateTime<Utc>,
+    ) -> Result<Vec<ArrivalEvent>, Error> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let previous: HashSet<String> = {
+            let mut stmt = tx.prepare("SELECT vehicle_key FROM watch_snapshots WHERE watch_id = ?1")?;
+            let keys = stmt
+                .query_map(params![watch.id], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            keys
+        };
+        let is_baseline = watch.last_run_at.is_none() && previous.is_empty();
+
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+        for (key, vehicle) in vehicles {
+            if !seen.insert(key.as_str()) || is_baseline || previous.contains(key) {
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO arrival_events (watch_id, watch_name, vehicle_key, vehicle_json, detected_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![watch.id, watch.name, key, serde_json::to_string(vehicle)?, run_at],
+            )?;
+            events.push(ArrivalEvent {
+                id: tx.last_insert_rowid(),
+                watch_id: watch.id,
+                watch_name: watch.name.clone(),
+                vehicle_key: key.clone(),
+                vehicle: vehicle.clone(),
+                detected_at: run_at,
+            });
+        }
+
+        for key in previous.iter().filter(|key| !seen.contains(key.as_str())) {
+            tx.execute(
+                "DELETE FROM watch_snapshots WHERE watch_id = ?1 AND vehicle_key = ?2",
+                params![watch.id, key],
+            )?;
+        }
+        for key in seen.into_iter().filter(|key| !previous.contains(*key)) {
+            tx.execute(
+                "INSERT INTO watch_snapshots (watch_id, vehicle_key) VALUES (?1, ?2)",
+                params![watch.id, key],
+            )?;
+        }
+        tx.execute(
+            "UPDATE watches SET last_run_at = ?2 WHERE id = ?1",
+            params![watch.id, run_at],
+        )?;
+
+        tx.commit()?;
+        Ok(events)
+    }
+
+    /// Arrival events, newest first, optionally for one watch and/or since a time.
+    pub fn list_events(
+        &self,
+        watch_id: Option<i64>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ArrivalEvent>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, watch_id, watch_name, vehicle_key, vehicle_json, detected_at FROM arrival_events
+             WHERE (?1 IS NULL OR watch_id = ?1) AND (?2 IS NULL OR detected_at >= ?2)
+             ORDER BY detected_at DESC, id DESC",
+        )?;
+        let rows = stmt
+            .query_map(params![watch_id, since], event_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter().map(into_arrival_event).collect()
+    }
+
+    /// Arrival events recorded after the event `after_id`, oldest first.
+    pub fn events_after(&self, after_id: i64) -> Result<Vec<ArrivalEvent>, Error> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, watch_id, watch_name, vehicle_key, vehicle_json, detected_at FROM arrival_events
+             WHERE id > ?1
+             ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![after_id], event_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.into_iter().map(into_arrival_event).collect()
+    }
+
+    /// The id of the last arrival event `notifier` has sent. A notifier asking for
+    /// the first time starts from the newest event rather than the whole history.
+    pub fn notifier_cursor(&self, notifier: &str) -> Result<i64, Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO notifier_cursors (notifier, last_event_id)
+             SELECT ?1, COALESCE(MAX(id), 0) FROM arrival_events",
+            params![notifier],
+        )?;
+        let last_event_id = conn.query_row(
+            "SELECT last_event_id FROM notifier_cursors WHERE notifier = ?1",
+            params![notifier],
+            |row| row.get(0),
+        )?;
+        Ok(last_event_id)
+    }
+
+    pub fn set_notifier_cursor(&self, notifier: &str, last_event_id: i64) -> Result<(), Error> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO notifier_cursors (notifier, last_event_id) VALUES (?1, ?2)
+             ON CONFLICT(notifier) DO UPDATE SET last_event_id = excluded.last_event_id",
+            params![notifier, last_event_id],
+        )?;
+        Ok(())
+    }
+}
+
+type EventRow = (i64, i64, String, String, String, DateTime<Utc>);
+
+fn event_row(row: &rusqlite::Row) -> rusqlite::Result<EventRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+}
+
+fn into_arrival_event(row: EventRow) -> Result<ArrivalEvent, Error> {
+    let (id, watch_id, watch_name, vehicle_key, vehicle_json, detected_at) = row;
+    Ok(ArrivalEvent {
+        id,
+        watch_id,
+        watch_name,
+        vehicle_key,
+        vehicle: serde_json::from_str(&vehicle_json)?,
+        detected_at,
+    })
+}
+
+type WatchRow = (i64, String, String, String, DateTime<Utc>, Option<DateTime<Utc>>);
+
+fn watch_row(row: &rusqlite::Row) -> rusqlite::Result<WatchRow> {
+    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+}
+
+fn into_saved_search(row: WatchRow) -> Result<SavedSearch, Error> {
+    let (id, name, owner, search_json, created_at, last_run_at) = row;
+    Ok(SavedSearch {
+        id,
+        name,
+        owner,
+        search: serde_json::from_str(&search_json)?,
+        created_at,
+        last_run_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::vehicle_key;
+    use crate::models::SearchRadius;
+    use crate::parser::parse_junkyard_page;
+    use chrono::Duration;
+
+    #[test]
+    fn creates_lists_and_deletes_watches() {
+        let watches = WatchStore::open_in_memory().unwrap();
+        let search = SearchRequest {
+            make: "subaru".to_string(),
+            models: vec!["impreza wagon".to_string()],
+            year_min: Some(2002),
+            year_max: Some(2007),
+            zip_code: "94560".to_string(),
+            distance: Some(SearchRadius::Miles50),
+            parser: None,
+            store_id: None,
+            sort: None,
+            max_miles: None,
+        };
+
+        let saved = watches.create("wagon donors", "sam", &search).unwrap();
+        watches.create("other", "alex", &search).unwrap();
+
+        let mine = watches.list(Some("sam")).unwrap();
+        assert_eq!(mine.len(), 1);
+        assert_eq!(mine[0].search.models, ["impreza wagon"]);
+        assert_eq!(watches.list(None).unwrap().len(), 2);
+
+        assert!(watches.delete(saved.id).unwrap());
+        assert!(!watches.delete(saved.id).unwrap());
+        assert!(watches.get(saved.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn reports_vehicles_that_leave_and_come_back() {
+        let watches = WatchStore::open_in_memory().unwrap();
+        let search: SearchRequest = serde_json::from_value(serde_json::json!({
+            "make": "Subaru", "model": "Impreza Wagon", "zip_code": "94560"
+        }))
+        .unwrap();
+        let mut watch = watches.create("wagons", "sam", &search).unwrap();
+        let vehicles: Vec<_> = parse_junkyard_page(include_str!("../firecrawl_sample_output.md"), "")
+            .into_iter()
+            .map(|item| (vehicle_key(&item), item))
+            .collect();
+        let start = Utc::now();
+
+        // Baseline, then the car leaves the yard's listing, then it's back
+        let runs = [(0, &vehicles[..], 0), (1, &[][..], 0), (2, &vehicles[..], 1), (3, &vehicles[..], 0)];
+        for (hours, run, reported) in runs {
+            let arrivals = watches.record_run(&watch, run, start + Duration::hours(hours)).unwrap();
+            assert_eq!(arrivals.len(), reported, "run {} reported {:?}", hours, arrivals);
+            watch = watches.get(watch.id).unwrap().unwrap();
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use std::error::Error as StdError;
+use std::time::Duration;
+
+use crate::models::{ArrivalEvent, JunkyardItem, Store};
+use crate::notifier::{Notifier, NotifyError};
+
+/// Header carrying `sha256=<hex HMAC of the body>` when a secret is configured.
+pub const SIGNATURE_HEADER: &str = "X-Junkyard-Signature";
+
+#[derive(Debug)]
+pub enum Error {
+    /// The HTTP client could not be set up
+    Client(reqwest::Error),
+    RequestFailed(reqwest::Error),
+    Rejected { status: u16, body: String },
+    /// Some events of a batch could not be delivered, by event id
+    Undelivered { total: usize, failures: Vec<(i64, Error)> },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Client(e) => write!(f, "Could not build the webhook client: {}", e),
+            Error::RequestFailed(e) => write!(f, "Webhook request failed: {}", e),
+            Error::Rejected { status, body } => {
+                write!(f, "Webhook returned status: {}, body: {}", status, body)
+            }
+            Error::Undelivered { total, failures } => {
+                write!(f, "{} of {} webhook deliveries failed", failures.len(), total)?;
+                for (event_id, e) in failures {
+                    write!(f, "; event {}: {}", event_id, e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl Error {
+    /// Whether posting again might succeed: network errors, 429 and 5xx. Other
+    /// 4xx responses mean the receiver won't take the event.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RequestFailed(_) => true,
+            Error::Rejected { status, .. } => *status == 429 || *status >= 500,
+            Error::Client(_) | Error::Undelivered { .. } => false,
+        }
+    }
+}
+
+/// JSON body POSTed for every new arrival.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub event: &'static str,
+    pub event_id: i64,
+    pub search_name: &'a str,
+    pub watch_id: i64,
+    pub vehicle_key: &'a str,
+    pub vehicle: &'a JunkyardItem,
+    pub store: Option<&'a Store>,
+    pub row: Option<&'a str>,
+    pub set_date: Option<chrono::DateTime<chrono::Utc>>,
+    pub photo_url: Option<&'a str>,
+    pub detected_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl<'a> From<&'a ArrivalEvent> for WebhookPayload<'a> {
+    fn from(event: &'a ArrivalEvent) -> Self {
+        Self {
+            event: "vehicle.arrived",
+            event_id: event.id,
+            search_name: &event.watch_name,
+            watch_id: event.watch_id,
+            vehicle_key: &event.vehicle_key,
+            vehicle: &event.vehicle,
+            store: event.vehicle.store.as_ref(),
+            row: event.vehicle.row.as_deref(),
+            set_date: event.vehicle.added_date,
+            photo_url: event.vehicle.image_urls.first().map(String::as_str),
+            detected_at: event.detected_at,
+        }
+    }
+}
+
+/// POSTs each arrival to a webhook URL, optionally HMAC-SHA256 signed.
+pub struct WebhookNotifier {
+    url: String,
+    secret: Option<String>,
+    max_retries: u32,
+    retry_base: Duration,
+    client: Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> Result<Self, Error> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(Error::Client)?;
+        Ok(Self {
+            url: url.into(),
+            secret: None,
+            max_retries: 3,
+            retry_base: Duration::from_secs(1),
+            client,
+        })
+    }
+
+    /// Sign every body with this shared secret.
+    pub fn with_secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_retry_base(mut self, retry_base: Duration) -> Self {
+        self.retry_base = retry_base;
+        self
+    }
+
+    async fn post_with_retry(&self, body: &str) -> Result<(), Error> {
+        let mut attempt = 0;
+        loop {
+            match self.post_once(body).await {
+                Ok(()) => return Ok(()),
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    let ceiling_ms = (self.retry_base.as_millis() as u64)
+                        .saturating_mul(2u64.saturating_pow(attempt));
+                    let delay = rand::thread_rng().gen_range(ceiling_ms / 2..=ceiling_ms);
+                    println!("{}, retrying in {}ms", e, delay);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn post_once(&self, body: &str) -> Result<(), Error> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, format!("sha256={}", sign(secret, body)));
+        }
+
+        let response = request.send().await.map_err(Error::RequestFailed)?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Rejected { status, body });
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    /// Sends every event, even after one fails, and reports all failures together.
+    async fn notify(&self, events: &[ArrivalEvent]) -> Result<(), NotifyError> {
+        let mut failures = Vec::new();
+        for event in events {
+            let body = serde_json::to_string(&WebhookPayload::from(event))?;
+            if let Err(e) = self.post_with_retry(&body).await {
+                failures.push((event.id, e));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(Error::Undelivered { total: events.len(), failures }))
+        }
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of `body`, as sent in [`SIGNATURE_HEADER`].
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_junkyard_page;
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::sync::{Arc, Mutex};
+    use crate::test_support::spawn_server;
+
+    type Received = Arc<Mutex<Vec<(Option<String>, String)>>>;
+
+    async fn receive(State(received): State<Received>, headers: HeaderMap, body: String) -> StatusCode {
+        let mut received = received.lock().unwrap();
+        let signature = headers
+            .get(SIGNATURE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        received.push((signature, body));
+        // Fail the first delivery to exercise the retry
+        if received.len() == 1 {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    #[tokio::test]
+    async fn posts_signed_payload_and_retries() {
+        let received: Received = Arc::default();
+        let app = Router::new().route("/hook", post(receive)).with_state(received.clone());
+        let addr = spawn_server(app).await;
+
+        let vehicle = parse_junkyard_page(include_str!("../firecrawl_sample_output.md"), "").remove(0);
+        let event = ArrivalEvent {
+            id: 1,
+            watch_id: 7,
+            watch_name: "wagon donors".to_string(),
+            vehicle_key: "47:2005_subaru_impreza_wagon_132".to_string(),
+            vehicle,
+            detected_at: chrono::Utc::now(),
+        };
+
+        let notifier = WebhookNotifier::new(format!("http://{}/hook", addr))
+            .unwrap()
+            .with_secret("s3cret")
+            .with_retry_base(Duration::from_millis(1));
+        notifier.notify(&[event]).await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (signature, body) = &received[1];
+        assert_eq!(signature.as_deref(), Some(format!("sha256={}", sign("s3cret", body)).as_str()));
+
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["search_name"], "wagon donors");
+        assert_eq!(payload["row"], "132");
+        assert_eq!(payload["store"]["name"], "Newark");
+        assert!(payload["photo_url"].as_str().unwrap().starts_with("https://cdn.row52.com/"));
+    }
+
+    #[tokio::test]
+    async fn attempts_every_event_of_a_batch() {
+        // Rejects the first event for good, accepts the rest; a 410 isn't retried
+        let received: Arc<Mutex<Vec<i64>>> = Arc::default();
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(|State(received): State<Arc<Mutex<Vec<i64>>>>, body: String| async move {
+                    let payload: serde_json::Value = serde_json::from_str(&body).unwrap();
+                    let event_id = payload["event_id"].as_i64().unwrap();
+                    received.lock().unwrap().push(event_id);
+                    if event_id == 1 {
+                        StatusCode::GONE
+                    } else {
+                        StatusCode::OK
+                    }
+                }),
+            )
+            .with_state(received.clone());
+        let addr = spawn_server(app).await;
+
+        let vehicle = parse_junkyard_page(include_str!("../firecrawl_sample_output.md"), "").remove(0);
+        let events: Vec<_> = (1..=3)
+            .map(|id| ArrivalEvent {
+                id,
+                watch_id: 7,
+                watch_name: "wagon donors".to_string(),
+                vehicle_key: format!("47:vehicle_{}", id),
+                vehicle: vehicle.clone(),
+                detected_at: chrono::Utc::now(),
+            })
+            .collect();
+
+        let notifier = WebhookNotifier::new(format!("http://{}/hook", addr))
+            .unwrap()
+            .with_retry_base(Duration::from_millis(1));
+        let error = notifier.notify(&events).await.unwrap_err();
+        assert!(error.to_string().starts_with("1 of 3 webhook deliveries failed; event 1:"), "{}", error);
+        assert_eq!(*received.lock().unwrap(), [1, 2, 3]);
+    }
+}